@@ -5,9 +5,10 @@ use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 use std::path::PathBuf;
 
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
+use indexmap::IndexMap;
 
-type NodePathComponents = Vec<String>;
+pub(crate) type NodePathComponents = Vec<String>;
 
 #[derive(Clone, Debug, Default)]
 pub struct NodePath {
@@ -24,6 +25,26 @@ pub struct NodeProperties {
     pub parent: Option<NodePath>,
     #[serde(skip)]
     pub record_file: PathBuf,
+    /// Where the node is recorded in `record_file`. If the node is not read from `record_file`
+    /// (for example, it's added or moved in memory), this field is `None`
+    #[serde(skip)]
+    pub span: Option<Span>,
+}
+
+/// The span of a node's object in its record file, like a JSON object or a TOML table.
+/// Lines and columns start from 1, and columns are counted in characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset of the start of the object, like `{` in JSON
+    pub start: usize,
+    /// Byte offset after the object
+    pub end: usize,
+    pub start_line: usize,
+    pub start_column: usize,
+    /// Line of the last character of the object, like `}` in JSON
+    pub end_line: usize,
+    /// Column of the last character of the object
+    pub end_column: usize,
 }
 
 /// Direct node structure, i.e. the node that truly has valuable values
@@ -35,8 +56,25 @@ pub struct DirectNode {
     /// The properties that are related to the node itself, but not the truly valuable information.
     pub node_properties: NodeProperties,
 
-    /// Customized properties in a direct node.
-    pub properties: HashMap<String, String>,
+    /// Customized properties in a direct node, in the order they are recorded.
+    pub properties: IndexMap<String, PropertyValue>,
+
+    /// If the node is resolved from a link node, the `target` of the link node; else `None`.
+    pub link_target: Option<PathBuf>,
+}
+
+/// Value of a customized property. Like a JSON value, it can be nested in arrays and objects
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<PropertyValue>),
+    /// Entries are in the order they are recorded
+    Object(IndexMap<String, PropertyValue>),
 }
 
 /// Link node structure. Links to another Wispha file
@@ -51,13 +89,23 @@ pub struct LinkNode {
 }
 
 /// Wispha node structure
-#[derive(Debug, Serialize)]
-#[serde(tag = "type")]
+#[derive(Debug)]
 pub enum Node {
     Direct(DirectNode),
     Link(LinkNode),
 }
 
+/// Order of children when a Wispha tree is serialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildrenOrder {
+    /// The order in which children are recorded
+    AsAuthored,
+    /// Sorted by name
+    Alphabetical,
+    /// Directories (link nodes, or direct nodes with children) first, then files. Each part is sorted by name
+    DirectoriesFirst,
+}
+
 /// Configuration of a Wispha tree
 #[derive(Debug, Clone)]
 pub struct TreeConfig {
@@ -71,6 +119,6 @@ pub(crate) struct InnerTree {
     pub config: TreeConfig
 }
 
-/// Wispha tree structure
-#[derive(Debug)]
+/// Wispha tree structure. A cloned tree shares the nodes with the original one
+#[derive(Debug, Clone)]
 pub struct Tree(pub(crate) Rc<RefCell<InnerTree>>);
\ No newline at end of file
@@ -0,0 +1,135 @@
+//! Decide which files should not be included in a Wispha tree.
+//!
+//! The rules follow `.gitignore` semantics, including nested ignore files,
+//! negation with `!` and directory-only patterns ending with `/`.
+
+use crate::strings::*;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Ignore rules of a project directory.
+///
+/// In each directory under `root`, `.gitignore` and `.wisphaignore` are read when needed,
+/// and rules in `.wisphaignore` take precedence. Rules in deeper directories take precedence over
+/// those in their ancestors. Extra patterns given by `excludes` take precedence over all ignore files.
+///
+/// Like git, the `.git` directory is always ignored.
+pub struct Ignorer {
+    root: PathBuf,
+    excludes: Gitignore,
+    // ignore rules of each directory, loaded lazily
+    matchers: RefCell<HashMap<PathBuf, Gitignore>>,
+}
+
+impl Ignorer {
+    /// Create ignore rules for `root` directory, with extra gitignore-style patterns `excludes`
+    /// which are relative to `root`
+    pub fn new(root: &Path, excludes: &[String]) -> Result<Ignorer, Error> {
+        let mut builder = GitignoreBuilder::new(root);
+        for exclude in excludes {
+            builder.add_line(None, exclude)
+                .map_err(|error| Error::InvalidPattern((exclude.clone(), error)))?;
+        }
+        let excludes = builder.build()
+            .map_err(|error| Error::InvalidPattern((excludes.join(" "), error)))?;
+        Ok(Ignorer {
+            root: root.to_path_buf(),
+            excludes,
+            matchers: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `path` should be ignored, either by itself or because one of its parent directories is.
+    /// `path` should be under `root`, otherwise it's not ignored.
+    ///
+    /// Like git, a path in an ignored directory can't be included again by rules in deeper directories
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> Result<bool, Error> {
+        let relative_path = match path.strip_prefix(&self.root) {
+            Ok(relative_path) => relative_path,
+            Err(_) => return Ok(false)
+        };
+        let mut current_path = self.root.clone();
+        let mut components = relative_path.components().peekable();
+        while let Some(component) = components.next() {
+            current_path.push(component);
+            // Only the last component may be a file
+            let is_component_dir = components.peek().is_some() || is_dir;
+            if is_component_dir && component.as_os_str() == GIT_DIR {
+                return Ok(true);
+            }
+            if self.is_matched(&current_path, is_component_dir)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Whether `path` itself is ignored by the rules, regardless of its parent directories
+    fn is_matched(&self, path: &Path, is_dir: bool) -> Result<bool, Error> {
+        match self.excludes.matched(path, is_dir) {
+            Match::Ignore(_) => return Ok(true),
+            Match::Whitelist(_) => return Ok(false),
+            Match::None => {}
+        }
+        let mut dir = path.parent();
+        while let Some(current_dir) = dir {
+            if !current_dir.starts_with(&self.root) {
+                break;
+            }
+            match self.matcher(current_dir)?.matched(path, is_dir) {
+                Match::Ignore(_) => return Ok(true),
+                Match::Whitelist(_) => return Ok(false),
+                Match::None => {}
+            }
+            dir = current_dir.parent();
+        }
+        Ok(false)
+    }
+
+    fn matcher(&self, dir: &Path) -> Result<Gitignore, Error> {
+        if let Some(matcher) = self.matchers.borrow().get(dir) {
+            return Ok(matcher.clone());
+        }
+        let mut builder = GitignoreBuilder::new(dir);
+        for file_name in &[GITIGNORE_FILE, WISPHAIGNORE_FILE] {
+            let file = dir.join(file_name);
+            if file.is_file() {
+                if let Some(error) = builder.add(&file) {
+                    return Err(Error::InvalidIgnoreFile((file, error)));
+                }
+            }
+        }
+        let matcher = builder.build()
+            .map_err(|error| Error::InvalidIgnoreFile((dir.to_path_buf(), error)))?;
+        self.matchers.borrow_mut().insert(dir.to_path_buf(), matcher.clone());
+        Ok(matcher)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// A pattern given to `Ignorer::new` is invalid
+    InvalidPattern((String, ignore::Error)),
+    /// An ignore file can't be read or contains invalid patterns
+    InvalidIgnoreFile((PathBuf, ignore::Error)),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            InvalidPattern((pattern, error)) => format!("Invalid pattern {}: {}", pattern, error),
+            InvalidIgnoreFile((path, error)) => format!("Invalid ignore file {}: {}", path.to_str().unwrap(), error),
+        };
+        write!(f, "{}", message)
+    }
+}
@@ -5,8 +5,12 @@
 //! * Core structs definitions of Wispha terms
 //! * Serialization and deserialization
 //! * Wispha tree manipulator
+//! * Resolvers that load the files of link nodes, from filesystem or memory
+//! * Ignore rules for files that should not be in a Wispha tree
 
 pub mod core;
 pub mod serde;
-mod manipulator;
-mod strings;
+pub mod manipulator;
+pub mod ignorer;
+pub mod resolver;
+pub mod strings;
@@ -1,12 +1,16 @@
 use crate::core::*;
 use crate::strings::*;
+use crate::serde::de;
+use crate::resolver::Resolver;
 
 use std::fmt;
 use std::error;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf, Component};
+
+use indexmap::IndexMap;
 
 impl NodePath {
     /// Create a root node path
@@ -17,32 +21,39 @@ impl NodePath {
         }
     }
 
-    /// Convert the node path to an absolute unix-style path string
-    pub fn to_string(&self) -> String {
-        format!("{root}{components}", root=ROOT, components=self.components.join(PATH_SEPARATOR))
+    /// Convert an absolute unix-style path string to node path. `.` and `..` components are resolved.
+    ///
+    /// If path string is not absolute, return `Error::NodePathMustBeAbsolute`
+    pub fn from(raw_path: &str, tree: &Tree) -> Result<NodePath, Error> {
+        if raw_path.starts_with(ROOT) {
+            Ok(NodePath::new(tree).join(raw_path))
+        } else {
+            Err(Error::NodePathMustBeAbsolute(raw_path.to_string()))
+        }
     }
 
-    /// Convert an absolute unix-style path string to node path.
+    /// Resolve a unix-style path string against the current node path, like `cd` in a shell.
     ///
-    /// If path string is not absolute, return `Error::NodePathMustBeAbsolute`
-    pub fn from(raw_path: &String, tree: &Tree) -> Result<NodePath, Error> {
-        let mut path = raw_path.clone();
-        if path.starts_with("/") {
-            path = path[1..].to_string();
-            if path.ends_with("/") {
-                path.pop();
-            }
-            let components = if path.is_empty() {
-                vec![]
-            } else {
-                path.split("/").map(|component| component.to_string()).collect::<Vec<String>>()
-            };
-            Ok(NodePath {
-                components,
-                tree: Rc::downgrade(&tree.0)
-            })
+    /// An absolute `raw_path` starts from root. `.` is the node itself, and `..` is its parent.
+    /// The parent of root is root itself.
+    pub fn join(&self, raw_path: &str) -> NodePath {
+        let mut components = if raw_path.starts_with(ROOT) {
+            vec![]
         } else {
-            Err(Error::NodePathMustBeAbsolute(raw_path.clone()))
+            self.components.clone()
+        };
+        for component in raw_path.split(PATH_SEPARATOR) {
+            match component {
+                "" | "." => {},
+                ".." => {
+                    components.pop();
+                },
+                _ => components.push(component.to_string()),
+            }
+        }
+        NodePath {
+            components,
+            tree: self.tree.clone()
         }
     }
 
@@ -59,7 +70,7 @@ impl NodePath {
     /// The parent node path of current node path. Return `None` if current node path is root
     pub fn parent(&self) -> Option<NodePath> {
         let mut components = self.components.clone();
-        if let Some(_) = components.pop() {
+        if components.pop().is_some() {
             Some(NodePath {
                 components,
                 tree: self.tree.clone()
@@ -81,7 +92,7 @@ impl NodePath {
 
 impl fmt::Display for NodePath {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.to_string())
+        write!(f, "{root}{components}", root=ROOT, components=self.components.join(PATH_SEPARATOR))
     }
 }
 
@@ -101,6 +112,95 @@ impl Node {
             None
         }
     }
+
+    pub fn get_direct_mut(&mut self) -> Option<&mut DirectNode> {
+        if let Node::Direct(direct_node) = self {
+            Some(direct_node)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn node_properties_mut(&mut self) -> &mut NodeProperties {
+        use Node::*;
+        match self {
+            Direct(direct_node) => &mut direct_node.node_properties,
+            Link(link_node) => &mut link_node.node_properties,
+        }
+    }
+}
+
+impl DirectNode {
+    /// Get the property at `key_path`.
+    ///
+    /// `key_path` is a key, or keys and array indices joined by `.` to address a nested value, e.g. `owner.team` or `tags.0`.
+    /// A key containing `.` itself is found as a whole first.
+    pub fn get_property(&self, key_path: &str) -> Option<&PropertyValue> {
+        if let Some(value) = self.properties.get(key_path) {
+            return Some(value);
+        }
+        let mut components = key_path.split(KEY_PATH_SEPARATOR);
+        let mut value = self.properties.get(components.next()?)?;
+        for component in components {
+            value = value.get(component)?;
+        }
+        Some(value)
+    }
+}
+
+impl PropertyValue {
+    /// Get the value of `key` in an object, or the value at index `key` in an array
+    pub fn get(&self, key: &str) -> Option<&PropertyValue> {
+        use PropertyValue::*;
+        match &self {
+            Object(map) => map.get(key),
+            Array(values) => values.get(key.parse::<usize>().ok()?),
+            _ => None
+        }
+    }
+
+    // The value of `key` in an object, which is created as an empty object if it does not exist,
+    // or the value at index `key` in an array
+    fn get_or_insert_mut(&mut self, key: &str) -> Option<&mut PropertyValue> {
+        use PropertyValue::*;
+        match self {
+            Object(map) => Some(map.entry(key.to_string()).or_insert_with(|| Object(IndexMap::new()))),
+            Array(values) => values.get_mut(key.parse::<usize>().ok()?),
+            _ => None
+        }
+    }
+
+    // The value of `key` in an object, or the value at index `key` in an array
+    fn get_mut(&mut self, key: &str) -> Option<&mut PropertyValue> {
+        use PropertyValue::*;
+        match self {
+            Object(map) => map.get_mut(key),
+            Array(values) => values.get_mut(key.parse::<usize>().ok()?),
+            _ => None
+        }
+    }
+}
+
+/// Strings are displayed without quotes. Arrays are displayed as `[a, b]`, and objects as `{key: value, ...}`
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use PropertyValue::*;
+        match &self {
+            Null => write!(f, "null"),
+            Bool(value) => write!(f, "{}", value),
+            Integer(value) => write!(f, "{}", value),
+            Float(value) => write!(f, "{}", value),
+            String(value) => write!(f, "{}", value),
+            Array(values) => write!(f, "[{}]", values.iter()
+                                                     .map(|value| value.to_string())
+                                                     .collect::<Vec<_>>()
+                                                     .join(", ")),
+            Object(map) => write!(f, "{{{}}}", map.iter()
+                                                  .map(|(key, value)| format!("{}: {}", key, value))
+                                                  .collect::<Vec<_>>()
+                                                  .join(", ")),
+        }
+    }
 }
 
 impl InnerTree {
@@ -113,12 +213,12 @@ impl InnerTree {
 
     pub(crate) fn root(&self) -> Option<Rc<RefCell<Node>>> {
         let root_path = vec![];
-        self.nodes.get(&root_path).map(|node_ref| Rc::clone(&node_ref))
+        self.nodes.get(&root_path).map(Rc::clone)
     }
 
     fn get_node(&self, node_path: &NodePath) -> Option<Rc<RefCell<Node>>> {
         self.nodes.get(&node_path.components)
-            .map(|node_ref| Rc::clone(node_ref))
+            .map(Rc::clone)
     }
 
     fn insert_node(&mut self, node_path: NodePath, node: Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
@@ -160,7 +260,7 @@ impl Tree {
         if let Some(parent) = node.borrow().node_properties().parent {
             parent.push(node.borrow().node_properties().name.clone())
         } else {
-            NodePath::new(&self)
+            NodePath::new(self)
         }
     }
 
@@ -177,64 +277,320 @@ impl Tree {
         }
     }
 
+    /// Get the node path of the node at os-related `path`, which is the reverse of `get_path_buf`.
+    /// A relative `path` is relative to the current directory.
+    ///
+    /// Only the link nodes on the way from root to the node are resolved, and the node itself is resolved as well.
+    /// If `path` is not in the directory of root, or some component of it has no node, return `Error::PathNotInTree`.
+    pub fn node_path_of<R>(&self,
+                           path: &Path,
+                           resolver: &R,
+                           preserved_keys: &Vec<&'static str>) -> Result<NodePath, Error>
+        where
+            R: Resolver + ?Sized {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir().map_err(Error::CurrentDirectoryNotAvailable)?.join(path)
+        };
+        let path = normalize_path(&path);
+        let mut node_path = NodePath::new(self);
+        self.resolve_link(&node_path, resolver, preserved_keys)?;
+        let root_dir = normalize_path(&self.get_path_buf(&node_path)?);
+        let relative = path.strip_prefix(&root_dir).map_err(|_| Error::PathNotInTree(path.clone()))?;
+        for component in relative.components() {
+            let name = component.as_os_str().to_string_lossy().to_string();
+            node_path = node_path.push(name);
+            if self.get_node(&node_path).is_none() {
+                return Err(Error::PathNotInTree(path));
+            }
+            self.resolve_link(&node_path, resolver, preserved_keys)?;
+        }
+        Ok(node_path)
+    }
+
     /// Clear all the nodes in the tree
     pub fn clear(&self) {
         self.0.borrow_mut().clear()
     }
 
-    /// Resolve to make sure tree has a direct node value of key `node_path`.
+    /// Add an empty direct node named `name` as the last child of `parent`, recorded in the same file as `parent`.
+    /// The node path of the new node is returned.
     ///
-    /// `resolve_handler` does two things:
-    /// * Convert `link_node`'s `target` to a node_str contains the `target`'s content
-    /// * Check if a direct loop exists, i.e. the `link_node`'s `target` is its `record_file`
-    pub fn resolve_node<F>(&self,
-                           node_path: &NodePath,
-                           resolve_handler: &F,
-                           preserved_keys: &Vec<&'static str>) -> Result<(), Error>
-        where
-            F: Fn(&LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
-        if let Some(node) = self.get_node(node_path) {
-            if let Node::Link(link_node) = &*node.borrow() {
-                let (path, node_str) = resolve_handler(link_node).map_err(|error| Error::Custom(error))?;
-                let parent_and_given_name = link_node.node_properties.parent.clone()
-                                                     .map(|parent| (parent, link_node.node_properties.name.clone()));
-                self.insert_nodes_from_str(&node_str,
-                                           path,
-                                           parent_and_given_name,
-                                           preserved_keys)
-                    .map_err(|de_error| Error::Custom(Box::new(de_error)))?;
-                // in case of `target` of `link_node` is still a link node
-                self.resolve_node(node_path, resolve_handler, preserved_keys)?;
-            }
-            Ok(())
+    /// `parent` must be a direct node, or `Error::UnresolvedLink` is returned.
+    /// If `parent` has a child named `name`, return `Error::NodeExists`.
+    pub fn add_child(&self, parent: &NodePath, name: &str) -> Result<NodePath, Error> {
+        check_name(name)?;
+        let parent_node = self.direct_node(parent)?;
+        let node_path = parent.push(name.to_string());
+        if self.get_node(&node_path).is_some() {
+            return Err(Error::NodeExists(node_path));
+        }
+        let node = DirectNode {
+            children: vec![],
+            node_properties: NodeProperties {
+                name: name.to_string(),
+                parent: Some(parent.clone()),
+                record_file: parent_node.borrow().node_properties().record_file,
+                span: None
+            },
+            properties: IndexMap::new(),
+            link_target: None
+        };
+        self.insert_node(node_path.clone(), Rc::new(RefCell::new(Node::Direct(node))));
+        parent_node.borrow_mut().get_direct_mut().unwrap().children.push(node_path.clone());
+        Ok(node_path)
+    }
+
+    /// Remove the node at `node_path` and all its descendants, and remove it from its parent's children.
+    /// The removed node is returned.
+    ///
+    /// Root can't be removed, otherwise return `Error::RootNotAllowed`.
+    pub fn remove_subtree(&self, node_path: &NodePath) -> Result<Rc<RefCell<Node>>, Error> {
+        let node = self.get_node(node_path).ok_or(Error::PathNotFound(node_path.clone()))?;
+        let parent = node_path.parent().ok_or(Error::RootNotAllowed)?;
+        self.0.borrow_mut().nodes.retain(|components, _| !components.starts_with(&node_path.components));
+        self.direct_node(&parent)?
+            .borrow_mut().get_direct_mut().unwrap()
+            .children.retain(|child| child.components != node_path.components);
+        Ok(node)
+    }
+
+    /// Rename the node at `node_path` to `new_name`, keeping its position among its siblings.
+    /// The new node path is returned.
+    ///
+    /// If a sibling named `new_name` exists, return `Error::NodeExists`.
+    pub fn rename_node(&self, node_path: &NodePath, new_name: &str) -> Result<NodePath, Error> {
+        let node = self.get_node(node_path).ok_or(Error::PathNotFound(node_path.clone()))?;
+        check_name(new_name)?;
+        let parent = if let Some(parent) = node_path.parent() {
+            parent
         } else {
-            if let Some(parent) = &node_path.parent() {
-                self.resolve_node(&parent, resolve_handler, preserved_keys)?;
-                if let Some(node) = self.get_node(node_path) {
-                    if let Node::Link(link_node) = &*node.borrow() {
-                        let (path, node_str) = resolve_handler(link_node).map_err(|error| Error::Custom(error))?;
-                        let parent_and_given_name = link_node.node_properties.parent.clone()
-                                                             .map(|parent| (parent, link_node.node_properties.name.clone()));
-                        self.insert_nodes_from_str(&node_str,
-                                                   path,
-                                                   parent_and_given_name,
-                                                   preserved_keys)
-                            .map_err(|de_error| Error::Custom(Box::new(de_error)))?;
-                        // in case of `target` of `link_node` is still a link node
-                        self.resolve_node(node_path, resolve_handler, preserved_keys)?;
-                    }
-                    Ok(())
+            // root has no node path component, so only its name is changed
+            node.borrow_mut().node_properties_mut().name = new_name.to_string();
+            return Ok(node_path.clone());
+        };
+        let new_path = parent.push(new_name.to_string());
+        if new_path.components == node_path.components {
+            return Ok(new_path);
+        }
+        if self.get_node(&new_path).is_some() {
+            return Err(Error::NodeExists(new_path));
+        }
+        let parent_node = self.direct_node(&parent)?;
+        self.relocate_subtree(node_path, &new_path, None);
+        for child in parent_node.borrow_mut().get_direct_mut().unwrap().children.iter_mut() {
+            if child.components == node_path.components {
+                *child = new_path.clone();
+            }
+        }
+        Ok(new_path)
+    }
+
+    /// Move the node at `from` to be the last child of `to_parent`. The new node path is returned.
+    ///
+    /// `to_parent` must be a direct node, or `Error::UnresolvedLink` is returned.
+    /// If `to_parent` is `from` or its descendant, return `Error::MoveIntoDescendant`.
+    /// If `to_parent` has a child with the same name, return `Error::NodeExists`.
+    ///
+    /// Moved nodes recorded in the same file as the old parent are regarded as recorded in the file of `to_parent`,
+    /// and the targets of such link nodes are updated accordingly. Nodes recorded in other files stay in their files.
+    pub fn move_node(&self, from: &NodePath, to_parent: &NodePath) -> Result<NodePath, Error> {
+        if self.get_node(from).is_none() {
+            return Err(Error::PathNotFound(from.clone()));
+        }
+        if to_parent.components.starts_with(&from.components) {
+            return Err(Error::MoveIntoDescendant((from.clone(), to_parent.clone())));
+        }
+        // `from` is not root, since root is the ancestor of every node
+        let old_parent = from.parent().unwrap();
+        if old_parent.components == to_parent.components {
+            return Ok(from.clone());
+        }
+        let new_parent_node = self.direct_node(to_parent)?;
+        let new_path = to_parent.push(from.name().unwrap());
+        if self.get_node(&new_path).is_some() {
+            return Err(Error::NodeExists(new_path));
+        }
+        let old_record_file = self.get_node(&old_parent).unwrap().borrow().node_properties().record_file;
+        let new_record_file = new_parent_node.borrow().node_properties().record_file;
+        self.relocate_subtree(from, &new_path, Some((old_record_file, new_record_file)));
+        self.direct_node(&old_parent)?
+            .borrow_mut().get_direct_mut().unwrap()
+            .children.retain(|child| child.components != from.components);
+        new_parent_node.borrow_mut().get_direct_mut().unwrap()
+            .children.push(new_path.clone());
+        Ok(new_path)
+    }
+
+    /// Set the customized property at `key_path` of the direct node at `node_path` to `value`.
+    /// The old value is returned if it exists.
+    ///
+    /// `key_path` addresses a nested value as in `DirectNode::get_property`, and missing objects on the way are created.
+    /// Keys used by the node structure itself, like `name` and `children`, can't be set.
+    pub fn set_property(&self, node_path: &NodePath, key_path: &str, value: PropertyValue) -> Result<Option<PropertyValue>, Error> {
+        let mut components = key_path.split(KEY_PATH_SEPARATOR).collect::<Vec<_>>();
+        if STRUCTURE_KEYS.contains(&key_path) || STRUCTURE_KEYS.contains(&components[0]) {
+            return Err(Error::KeyNotAllowed(key_path.to_string()));
+        }
+        let node = self.direct_node(node_path)?;
+        let mut node = node.borrow_mut();
+        let properties = &mut node.get_direct_mut().unwrap().properties;
+        if components.len() == 1 || properties.contains_key(key_path) {
+            return Ok(properties.insert(key_path.to_string(), value));
+        }
+        let last = components.pop().unwrap();
+        let mut parent = properties.entry(components[0].to_string()).or_insert_with(|| PropertyValue::Object(IndexMap::new()));
+        for component in &components[1..] {
+            parent = parent.get_or_insert_mut(component)
+                .ok_or_else(|| Error::InvalidKeyPath(key_path.to_string()))?;
+        }
+        match parent {
+            PropertyValue::Object(map) => Ok(map.insert(last.to_string(), value)),
+            PropertyValue::Array(values) => {
+                let index = last.parse::<usize>().ok()
+                    .filter(|index| *index <= values.len())
+                    .ok_or_else(|| Error::InvalidKeyPath(key_path.to_string()))?;
+                if index == values.len() {
+                    values.push(value);
+                    Ok(None)
                 } else {
-                    Err(Error::PathNotFound(node_path.clone()))
+                    Ok(Some(std::mem::replace(&mut values[index], value)))
+                }
+            },
+            _ => Err(Error::InvalidKeyPath(key_path.to_string()))
+        }
+    }
+
+    /// Remove the customized property at `key_path` of the direct node at `node_path`, keeping the order of the other properties.
+    /// The old value is returned if it exists.
+    ///
+    /// `key_path` addresses a nested value as in `DirectNode::get_property`.
+    pub fn remove_property(&self, node_path: &NodePath, key_path: &str) -> Result<Option<PropertyValue>, Error> {
+        let node = self.direct_node(node_path)?;
+        let mut node = node.borrow_mut();
+        let properties = &mut node.get_direct_mut().unwrap().properties;
+        if let Some(value) = properties.shift_remove(key_path) {
+            return Ok(Some(value));
+        }
+        let mut components = key_path.split(KEY_PATH_SEPARATOR).collect::<Vec<_>>();
+        if components.len() == 1 {
+            return Ok(None);
+        }
+        let last = components.pop().unwrap();
+        let mut parent = match properties.get_mut(components[0]) {
+            Some(parent) => parent,
+            None => return Ok(None)
+        };
+        for component in &components[1..] {
+            parent = match parent.get_mut(component) {
+                Some(value) => value,
+                None => return Ok(None)
+            };
+        }
+        Ok(match parent {
+            PropertyValue::Object(map) => map.shift_remove(last),
+            PropertyValue::Array(values) => last.parse::<usize>().ok()
+                .filter(|index| *index < values.len())
+                .map(|index| values.remove(index)),
+            _ => None
+        })
+    }
+
+    /// Record the direct node at `node_path` in `record_file`. Its descendants stay in their files.
+    ///
+    /// When the tree is saved, the node is written to `record_file`, and linked from its parent's file if they differ.
+    pub fn set_record_file(&self, node_path: &NodePath, record_file: PathBuf) -> Result<(), Error> {
+        let node = self.direct_node(node_path)?;
+        let mut node = node.borrow_mut();
+        let node_properties = node.node_properties_mut();
+        if node_properties.record_file != record_file {
+            node_properties.record_file = record_file;
+            // The node is not recorded in the new file yet
+            node_properties.span = None;
+        }
+        Ok(())
+    }
+
+    // The node at `node_path`, which must be a direct node
+    fn direct_node(&self, node_path: &NodePath) -> Result<Rc<RefCell<Node>>, Error> {
+        let node = self.get_node(node_path).ok_or(Error::PathNotFound(node_path.clone()))?;
+        if node.borrow().get_direct().is_none() {
+            return Err(Error::UnresolvedLink(node_path.clone()));
+        }
+        Ok(node)
+    }
+
+    // Move the nodes of subtree `from` to `to`, updating their node paths, names, parents and children.
+    // Children lists of the parents of `from` and `to` are not changed.
+    // If `record_files` is `(old, new)`, the nodes recorded in `old` are regarded as recorded in `new`.
+    fn relocate_subtree(&self, from: &NodePath, to: &NodePath, record_files: Option<(PathBuf, PathBuf)>) {
+        let rebase = |components: &NodePathComponents| -> NodePathComponents {
+            to.components.iter()
+              .chain(components[from.components.len()..].iter())
+              .cloned()
+              .collect()
+        };
+        let mut inner_tree = self.0.borrow_mut();
+        let moved_paths = inner_tree.nodes.keys()
+            .filter(|components| components.starts_with(&from.components))
+            .cloned()
+            .collect::<Vec<_>>();
+        for components in moved_paths {
+            let node = inner_tree.nodes.remove(&components).unwrap();
+            {
+                let mut node = node.borrow_mut();
+                let node_properties = node.node_properties_mut();
+                if components == from.components {
+                    node_properties.name = to.name().unwrap();
+                    node_properties.parent = to.parent();
+                } else if let Some(parent) = &mut node_properties.parent {
+                    parent.components = rebase(&parent.components);
+                }
+                if let Some((old_record_file, new_record_file)) = &record_files {
+                    if &node_properties.record_file == old_record_file {
+                        node_properties.record_file = new_record_file.clone();
+                        // The node is not recorded in the new file yet
+                        node_properties.span = None;
+                        if let Node::Link(link_node) = &mut *node {
+                            if link_node.target.is_relative() {
+                                let target_file = old_record_file.parent().unwrap().join(&link_node.target);
+                                link_node.target = relative_path(new_record_file.parent().unwrap(), &target_file);
+                            }
+                        }
+                    }
+                }
+                if let Some(direct_node) = node.get_direct_mut() {
+                    for child in direct_node.children.iter_mut() {
+                        child.components = rebase(&child.components);
+                    }
                 }
-            } else {
-                Err(Error::PathNotFound(node_path.clone()))
             }
+            inner_tree.nodes.insert(rebase(&components), node);
+        }
+    }
+
+    /// Resolve to make sure tree has a direct node value of key `node_path`.
+    ///
+    /// `resolver` loads the target files of link nodes.
+    ///
+    /// If a link's target is a file that is already being resolved (e.g. `a/LOOKME.json -> b/LOOKME.json -> a/LOOKME.json`),
+    /// return `Error::LinkCycle`
+    pub fn resolve_node<R>(&self,
+                           node_path: &NodePath,
+                           resolver: &R,
+                           preserved_keys: &Vec<&'static str>) -> Result<(), Error>
+        where
+            R: Resolver + ?Sized {
+        if self.get_node(node_path).is_none() {
+            let parent = node_path.parent().ok_or(Error::PathNotFound(node_path.clone()))?;
+            self.resolve_node(&parent, resolver, preserved_keys)?;
         }
+        self.resolve_link(node_path, resolver, preserved_keys)
     }
 
     /// Update the `tree`'s `nodes`, starting from `node_path`, with depth `depth`, to direct node,
-    /// using `resolve_handler` to convert from `PathBuf` to `Node`.
+    /// using `resolver` to convert from `PathBuf` to `Node`.
     ///
     /// If this function returns `Ok`, it means two things:
     /// * The `node_path` does exist in the tree
@@ -242,55 +598,182 @@ impl Tree {
     ///
     /// The `node_path` itself's `depth` is 0
     ///
-    /// `resolve_handler` does two things:
-    /// * Convert `link_node`'s `target` to a node_str contains the `target`'s content
-    /// * Check if a direct loop exists, i.e. the `link_node`'s `target` is its `record_file`
-    pub fn resolve_in_depth<F>(&self,
+    /// `resolver` loads the target files of link nodes.
+    ///
+    /// If a link's target is a file that is already being resolved, return `Error::LinkCycle`
+    pub fn resolve_in_depth<R>(&self,
                                node_path: &NodePath,
                                depth: usize,
-                               resolve_handler: &F,
+                               resolver: &R,
                                preserved_keys: &Vec<&'static str>) -> Result<(), Error>
         where
-            F: Fn(&LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
-        let node = self.get_node(node_path).ok_or(Error::PathNotFound(node_path.clone()))?;
-        let node = &*node.borrow();
-        match node {
-            Node::Direct(direct_node) => {
-                if depth > 0 {
-                    for child in &direct_node.children {
-                        self.resolve_in_depth(child,
-                                              depth - 1,
-                                              resolve_handler,
-                                              preserved_keys)?;
-                    }
+            R: Resolver + ?Sized {
+        self.resolve_link(node_path, resolver, preserved_keys)?;
+        if depth > 0 {
+            // After resolving link, there must be a direct node at `node_path`
+            let node = self.get_node(node_path).unwrap();
+            let node = &*node.borrow();
+            for child in &node.get_direct().unwrap().children {
+                self.resolve_in_depth(child,
+                                      depth - 1,
+                                      resolver,
+                                      preserved_keys)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Replace the link node at `node_path` with its target, until it is a direct node.
+    // `node_path` must exist in the tree, and its ancestors must be direct nodes.
+    fn resolve_link<R>(&self,
+                       node_path: &NodePath,
+                       resolver: &R,
+                       preserved_keys: &Vec<&'static str>) -> Result<(), Error>
+        where
+            R: Resolver + ?Sized {
+        let mut chain = self.record_file_chain(node_path);
+        // the target of the first link node, which is kept in the resolved direct node
+        let mut link_target = None;
+        loop {
+            let node = self.get_node(node_path).ok_or(Error::PathNotFound(node_path.clone()))?;
+            if let Node::Link(link_node) = &*node.borrow() {
+                if link_target.is_none() {
+                    link_target = Some(link_node.target.clone());
+                }
+                let record_file = normalize_path(&link_node.node_properties.record_file);
+                if chain.last() != Some(&record_file) {
+                    chain.push(record_file);
+                }
+                let (path, node_str) = resolver.resolve(link_node).map_err(Error::Custom)?;
+                let target = normalize_path(&path);
+                if let Some(index) = chain.iter().position(|record_file| record_file == &target) {
+                    let mut cycle = chain[index..].to_vec();
+                    cycle.push(target);
+                    return Err(Error::LinkCycle(cycle));
                 }
-            },
-            Node::Link(link_node) => {
-                let (path, node_str) = resolve_handler(link_node).map_err(|error| Error::Custom(error))?;
                 let parent_and_given_name = link_node.node_properties.parent.clone()
                                                      .map(|parent| (parent, link_node.node_properties.name.clone()));
                 self.insert_nodes_from_str(&node_str,
                                            path,
                                            parent_and_given_name,
                                            preserved_keys)
-                    .map_err(|de_error| Error::Custom(Box::new(de_error)))?;
-                // in case of `target` of `link_node` is still a link node
-                self.resolve_in_depth(node_path, depth, resolve_handler,
-                                      preserved_keys)?;
+                    .map_err(|de_error| Error::Deserialization(Box::new(de_error)))?;
+                // in case of `target` of `link_node` is still a link node, loop again
+                continue;
+            }
+            if link_target.is_some() {
+                node.borrow_mut().get_direct_mut().unwrap().link_target = link_target;
+            }
+            return Ok(());
+        }
+    }
+
+    // The record files of the ancestors of `node_path`, from root to its parent.
+    // Adjacent nodes recorded in the same file only contribute one element.
+    fn record_file_chain(&self, node_path: &NodePath) -> Vec<PathBuf> {
+        let mut chain: Vec<PathBuf> = vec![];
+        let mut ancestor = node_path.parent();
+        while let Some(ancestor_path) = ancestor {
+            if let Some(node) = self.get_node(&ancestor_path) {
+                let record_file = normalize_path(&node.borrow().node_properties().record_file);
+                if chain.last() != Some(&record_file) {
+                    chain.push(record_file);
+                }
             }
+            ancestor = ancestor_path.parent();
         }
+        chain.reverse();
+        chain
+    }
+}
+
+// A node name can't be empty or contain a path separator
+fn check_name(name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.contains(PATH_SEPARATOR) {
+        Err(Error::InvalidName(name.to_string()))
+    } else {
         Ok(())
     }
 }
 
+// The path of `path` relative to directory `base`, computed lexically.
+// If one of them is absolute and the other is not, `path` is returned.
+pub(crate) fn relative_path(base: &Path, path: &Path) -> PathBuf {
+    if base.is_absolute() != path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let base = normalize_path(base);
+    let path = normalize_path(path);
+    let base_components = base.components().collect::<Vec<_>>();
+    let path_components = path.components().collect::<Vec<_>>();
+    let common = base_components.iter().zip(path_components.iter())
+                                .take_while(|(base_component, path_component)| base_component == path_component)
+                                .count();
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push(Component::ParentDir);
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+// Lexically remove `.` and `..` components, so that different spellings of a file compare equal
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                if let Some(Component::Normal(_)) = normalized.components().next_back() {
+                    normalized.pop();
+                } else {
+                    normalized.push(component);
+                }
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
 #[derive(Debug)]
 pub enum Error {
     PathNotFound(NodePath),
     Custom(Box<dyn error::Error>),
-    NodePathMustBeAbsolute(String)
+    NodePathMustBeAbsolute(String),
+    /// Links form a cycle. The record files in the cycle are listed in order, and the first one is repeated at the end
+    LinkCycle(Vec<PathBuf>),
+    /// A node already exists at the node path
+    NodeExists(NodePath),
+    /// Moving a node into itself or its descendant
+    MoveIntoDescendant((NodePath, NodePath)),
+    /// Root node can't be removed
+    RootNotAllowed,
+    /// The node is a link node that has not been resolved, so it can't have properties or children changed
+    UnresolvedLink(NodePath),
+    /// A node name is empty or contains a path separator
+    InvalidName(String),
+    /// The key is used by the node structure itself
+    KeyNotAllowed(String),
+    /// A value on the key path is not an object or an array, or an array index is out of range
+    InvalidKeyPath(String),
+    /// The os-related path has no corresponding node in the tree
+    PathNotInTree(PathBuf),
+    CurrentDirectoryNotAvailable(std::io::Error),
+    /// The target of a link node is broken
+    Deserialization(Box<de::Error>),
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self {
+            Error::Deserialization(de_error) => Some(&**de_error),
+            _ => None
+        }
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
@@ -298,7 +781,21 @@ impl fmt::Display for Error {
         let message = match &self {
             PathNotFound(path) => format!("Path {} not found.", path),
             Custom(error) => format!("{}", error),
-            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path)
+            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path),
+            LinkCycle(cycle) => format!("Links form a cycle: {}.", cycle.iter()
+                                                                   .map(|path| path.to_string_lossy())
+                                                                   .collect::<Vec<_>>()
+                                                                   .join(" -> ")),
+            NodeExists(path) => format!("Node {} already exists.", path),
+            MoveIntoDescendant((from, to_parent)) => format!("Can't move {} into its descendant {}.", from, to_parent),
+            RootNotAllowed => String::from("Root node can't be removed."),
+            UnresolvedLink(path) => format!("Node {} is an unresolved link node.", path),
+            InvalidName(name) => format!("Invalid node name {}.", name),
+            KeyNotAllowed(key) => format!("Key {} can't be used as a property.", key),
+            InvalidKeyPath(key_path) => format!("Can't set property at key path {}.", key_path),
+            PathNotInTree(path) => format!("Path {} has no node in the tree.", path.to_string_lossy()),
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            Deserialization(de_error) => format!("{}", de_error),
         };
         write!(f, "{}", message)
     }
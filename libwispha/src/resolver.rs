@@ -0,0 +1,158 @@
+//! Resolvers that load the target files of link nodes.
+
+use crate::core::*;
+use crate::manipulator::normalize_path;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::iter::FromIterator;
+use std::path::PathBuf;
+
+/// Load the target file of a link node, returning the target's path and content.
+///
+/// Any `Fn(&LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>>` is a resolver.
+/// Links forming a cycle are detected by the tree when resolving, so a resolver doesn't need to check them.
+pub trait Resolver {
+    fn resolve(&self, link_node: &LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>>;
+}
+
+impl<F> Resolver for F
+    where
+        F: Fn(&LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
+    fn resolve(&self, link_node: &LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
+        self(link_node)
+    }
+}
+
+/// The file path that `link_node`'s `target` points to. A relative target is relative to the directory of the record file
+pub fn target_path(link_node: &LinkNode) -> PathBuf {
+    if link_node.target.is_absolute() {
+        normalize_path(&link_node.target)
+    } else {
+        let dir = link_node.node_properties.record_file.parent().unwrap();
+        normalize_path(&dir.join(&link_node.target))
+    }
+}
+
+/// Resolver that reads target files from filesystem
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsResolver;
+
+impl Resolver for FsResolver {
+    /// If the target is the record file of the link node itself, return `Error::LoopTarget`
+    fn resolve(&self, link_node: &LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
+        let path = target_path(link_node);
+        if path == normalize_path(&link_node.node_properties.record_file) {
+            return Err(Box::new(Error::LoopTarget(path)));
+        }
+        let node_str = fs::read_to_string(&path)
+            .map_err(|io_error| Error::CannotRead((path.clone(), io_error)))?;
+        Ok((path, node_str))
+    }
+}
+
+/// Resolver backed by a map from file paths to contents, for tests and embedding Wispha trees in programs.
+///
+/// Paths are compared after normalized, so `a/../b.json` and `b.json` are the same file.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> MemoryResolver {
+        MemoryResolver::default()
+    }
+
+    /// Add a file, or replace its content if it exists. The old content is returned
+    pub fn insert(&mut self, path: impl Into<PathBuf>, content: impl Into<String>) -> Option<String> {
+        self.files.insert(normalize_path(&path.into()), content.into())
+    }
+
+    /// Content of the file at `path`
+    pub fn get(&self, path: impl Into<PathBuf>) -> Option<&String> {
+        self.files.get(&normalize_path(&path.into()))
+    }
+}
+
+impl<P: Into<PathBuf>, S: Into<String>> FromIterator<(P, S)> for MemoryResolver {
+    fn from_iter<I: IntoIterator<Item = (P, S)>>(iter: I) -> Self {
+        let mut resolver = MemoryResolver::new();
+        for (path, content) in iter {
+            resolver.insert(path, content);
+        }
+        resolver
+    }
+}
+
+impl Resolver for MemoryResolver {
+    fn resolve(&self, link_node: &LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
+        let path = target_path(link_node);
+        let content = self.files.get(&path).ok_or_else(|| Error::FileNotFound(path.clone()))?;
+        Ok((path, content.clone()))
+    }
+}
+
+/// Resolver that remembers the files loaded by `inner`, so that each target file is loaded only once
+#[derive(Debug)]
+pub struct CachingResolver<R: Resolver> {
+    inner: R,
+    cache: RefCell<HashMap<PathBuf, (PathBuf, String)>>,
+}
+
+impl<R: Resolver> CachingResolver<R> {
+    pub fn new(inner: R) -> CachingResolver<R> {
+        CachingResolver {
+            inner,
+            cache: RefCell::new(HashMap::new())
+        }
+    }
+
+    /// Forget all loaded files, so that they are loaded by `inner` again
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear()
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: Resolver> Resolver for CachingResolver<R> {
+    /// Failures are not cached
+    fn resolve(&self, link_node: &LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
+        let key = target_path(link_node);
+        if let Some(resolved) = self.cache.borrow().get(&key) {
+            return Ok(resolved.clone());
+        }
+        let resolved = self.inner.resolve(link_node)?;
+        self.cache.borrow_mut().insert(key, resolved.clone());
+        Ok(resolved)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// A link node whose target is the file it is recorded in
+    LoopTarget(PathBuf),
+    CannotRead((PathBuf, std::io::Error)),
+    /// The file is not in `MemoryResolver`
+    FileNotFound(PathBuf),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            LoopTarget(path) => format!("A node in file {} has target to the file itself.", path.to_string_lossy()),
+            CannotRead((path, io_error)) => format!("Can't open file at {}: {}", path.to_string_lossy(), io_error),
+            FileNotFound(path) => format!("Can't find file at {}.", path.to_string_lossy()),
+        };
+        write!(f, "{}", message)
+    }
+}
@@ -1,24 +1,27 @@
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::error;
 use std::fmt;
 use std::path::PathBuf;
 
 use crate::core::*;
 use crate::strings::*;
+use crate::serde::span::{self, ObjectSpan};
+use crate::serde::format::{self, Format};
 
-use serde::Deserialize;
-use serde_json;
+use indexmap::IndexMap;
 
-#[derive(Debug, Deserialize)]
+type NodesWithPath = Vec<(NodePath, Rc<RefCell<Node>>)>;
+
+#[derive(Debug)]
 struct RawNode {
-    #[serde(flatten)]
-    properties: HashMap<String, String>,
+    properties: IndexMap<String, PropertyValue>,
     children: Option<Vec<Rc<RefCell<RawNode>>>>
 }
 
+#[derive(Default)]
 enum RawNodeType {
+    #[default]
     Direct,
     Link
 }
@@ -27,45 +30,179 @@ impl RawNodeType {
     fn from_str(s: &str) -> Option<RawNodeType> {
         use RawNodeType::*;
         match s {
-            "Direct" => Some(Direct),
-            "Link" => Some(Link),
+            _ if s == DIRECT => Some(Direct),
+            _ if s == LINK => Some(Link),
             _ => None
         }
     }
 }
 
-impl Default for RawNodeType {
-    fn default() -> Self {
-        RawNodeType::Direct
+/// Where an error occurs in a record file
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub record_file: PathBuf,
+    /// Line number, starting from 1
+    pub line: usize,
+    /// Column number in characters, starting from 1
+    pub column: usize,
+    /// Node path for the node being built. For a node whose name is unknown, it's the node path of its parent
+    pub node_path: NodePath,
+    /// Content of the line
+    pub source_line: String,
+}
+
+impl Location {
+    /// Render the line with a caret under the column, like
+    ///
+    /// ```text
+    ///  --> LOOKME.json:3:5
+    ///   |
+    /// 3 |     {"type": "Link"}
+    ///   |     ^
+    /// ```
+    pub fn snippet(&self) -> String {
+        let line_number = self.line.to_string();
+        let padding = " ".repeat(line_number.len());
+        // keep tabs, so that the caret is aligned with the source line
+        let caret_indent = self.source_line.chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        [
+            format!("{}--> {}:{}:{}", padding, self.record_file.to_string_lossy(), self.line, self.column),
+            format!("{} |", padding),
+            format!("{} | {}", line_number, self.source_line),
+            format!("{} | {}^", padding, caret_indent),
+        ].join("\n")
+    }
+}
+
+/// The record file being deserialized, used to locate errors
+struct Source<'a> {
+    record_file: &'a PathBuf,
+    text: &'a str,
+}
+
+impl<'a> Source<'a> {
+    // Location of byte `offset`. The start of the file is used if `offset` is unknown
+    fn location(&self, offset: Option<usize>, node_path: &NodePath) -> Location {
+        let (line, column) = span::line_column(self.text, offset.unwrap_or(0));
+        self.location_at(line, column, node_path)
+    }
+
+    fn location_at(&self, line: usize, column: usize, node_path: &NodePath) -> Location {
+        let source_line = self.text.lines().nth(line - 1).unwrap_or_default().to_string();
+        Location {
+            record_file: self.record_file.clone(),
+            line,
+            column,
+            node_path: node_path.clone(),
+            source_line
+        }
     }
 }
 
 impl RawNode {
+    // Build a node from `value`, which must be an object whose `children` is an array of objects.
+    // `node_path` is the node path of the node if its name is known, or its parent's
+    fn from_value(value: PropertyValue,
+                  span: Option<&ObjectSpan>,
+                  source: &Source,
+                  node_path: &NodePath) -> Result<RawNode, Error> {
+        let mut properties = if let PropertyValue::Object(properties) = value {
+            properties
+        } else {
+            return Err(Error::NotObject(source.location(span.map(|span| span.start), node_path)));
+        };
+        let children = match properties.shift_remove(CHILDREN) {
+            Some(PropertyValue::Array(children)) => {
+                let children = children.into_iter()
+                    .enumerate()
+                    .map(|(index, child)| {
+                        let child_span = span.and_then(|span| span.children.get(index));
+                        let child_path = match &child {
+                            PropertyValue::Object(child) => match child.get(NAME) {
+                                Some(PropertyValue::String(name)) => node_path.push(name.clone()),
+                                _ => node_path.clone()
+                            },
+                            _ => node_path.clone()
+                        };
+                        RawNode::from_value(child, child_span, source, &child_path)
+                            .map(|child| Rc::new(RefCell::new(child)))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Some(children)
+            },
+            Some(_) => {
+                let offset = span.and_then(|span| span.keys.get(CHILDREN).copied());
+                return Err(Error::NotArray((CHILDREN.to_string(), source.location(offset, node_path))));
+            },
+            None => None
+        };
+        Ok(RawNode {
+            properties,
+            children
+        })
+    }
+
+    // The value of `key`, which must be a string if it exists
+    fn string_property(&self,
+                       key: &str,
+                       span: Option<&ObjectSpan>,
+                       source: &Source,
+                       node_path: &NodePath) -> Result<Option<String>, Error> {
+        match self.properties.get(key) {
+            Some(PropertyValue::String(value)) => Ok(Some(value.clone())),
+            Some(_) => {
+                let offset = span.and_then(|span| span.keys.get(key).copied());
+                Err(Error::NotString((key.to_string(), source.location(offset, node_path))))
+            },
+            None => Ok(None)
+        }
+    }
+
     // external call:
     //      if is root, no parent, give tree.config.project_name as given_name;
     //      else give parent, give link_node.node_properties.name as given_name
     // recursive call:
     //      give parent, give given_name
     // The upmost node is in the tail of returned vec
+    //
+    // `span` locates the node in `source` for errors
     fn convert_to_nodes(raw_node: &Rc<RefCell<RawNode>>,
+                        span: Option<&ObjectSpan>,
                         parent: Option<NodePath>,
                         given_name: Option<String>,
                         tree: &Tree,
-                        record_file: &PathBuf,
-                        preserved_keys: &Vec<&'static str>) -> Result<Vec<(NodePath, Rc<RefCell<Node>>)>, Error> {
-        let raw_node_type = if let Some(node_type_str) = raw_node.borrow().properties.get("type") {
-            RawNodeType::from_str(node_type_str).ok_or(Error::UnknownType(node_type_str.to_owned()))?
+                        source: &Source,
+                        preserved_keys: &Vec<&'static str>) -> Result<NodesWithPath, Error> {
+        let record_file = source.record_file;
+        let start = span.map(|span| span.start);
+        // node path for errors before the name is known
+        let parent_path = parent.clone().unwrap_or_else(|| NodePath::new(tree));
+        // given name is prior to the recorded name
+        let name = if let Some(name) = given_name {
+            Some(name)
+        } else {
+            raw_node.borrow().string_property(NAME, span, source, &parent_path)?
+        };
+        let raw_node_type = if let Some(node_type_str) = raw_node.borrow().string_property(TYPE, span, source, &parent_path)? {
+            if let Some(raw_node_type) = RawNodeType::from_str(&node_type_str) {
+                raw_node_type
+            } else {
+                let node_path = match (&parent, &name) {
+                    (Some(parent), Some(name)) => parent.push(name.clone()),
+                    _ => parent_path
+                };
+                let offset = span.and_then(|span| span.keys.get(TYPE).copied());
+                return Err(Error::UnknownType((node_type_str, source.location(offset, &node_path))));
+            }
         } else {
             RawNodeType::default()
         };
+        let name = name.ok_or_else(|| Error::LackName(source.location(start, &parent_path)))?;
         match raw_node_type {
             RawNodeType::Direct => {
-                // given name is prior to the recorded name
-                let name = if let Some(name) = given_name {
-                    name
-                } else {
-                    raw_node.borrow().properties.get(NAME).cloned().ok_or(Error::LackName)?
-                };
                 let path = if let Some(parent) = &parent {
                     parent.push(name.clone())
                 } else {
@@ -76,13 +213,16 @@ impl RawNode {
                     name,
                     parent,
                     record_file: record_file.clone(),
+                    span: span.map(|span| span.to_span(source.text)),
                 };
 
                 // the vector of vector of children of children
                 let sub_node_children = if let Some(children) = &raw_node.borrow().children {
                     children.iter()
-                        .map(|sub_node| -> Result<_, Error> {
-                            RawNode::convert_to_nodes(sub_node, Some(path.clone()), None, tree, record_file, preserved_keys)
+                        .enumerate()
+                        .map(|(index, sub_node)| -> Result<_, Error> {
+                            let sub_span = span.and_then(|span| span.children.get(index));
+                            RawNode::convert_to_nodes(sub_node, sub_span, Some(path.clone()), None, tree, source, preserved_keys)
                         })
                         .collect::<Result<Vec<_>, Error>>()?
                 } else {
@@ -98,14 +238,21 @@ impl RawNode {
 
                 for key in keys {
                     if preserved_keys.contains(&key.as_str()) {
-                        return Err(Error::PreservedKey(key.clone()));
+                        let offset = span.and_then(|span| span.keys.get(key).copied());
+                        return Err(Error::PreservedKey((key.clone(), source.location(offset, &path))));
                     }
                 }
 
+                // `name` and `type` are stored in the node itself rather than in its properties
+                let mut properties = raw_node.properties.clone();
+                properties.shift_remove(NAME);
+                properties.shift_remove(TYPE);
+
                 let node = Rc::new(RefCell::new(Node::Direct(DirectNode {
                     children: sub_nodes,
                     node_properties,
-                    properties: raw_node.properties.clone(),
+                    properties,
+                    link_target: None,
                 })));
 
                 let mut nodes = sub_node_children.into_iter().flatten().collect::<Vec<_>>();
@@ -114,11 +261,6 @@ impl RawNode {
                 Ok(nodes)
             },
             RawNodeType::Link => {
-                let name = if let Some(name) = given_name {
-                    name
-                } else {
-                    raw_node.borrow().properties.get("name").cloned().ok_or(Error::LackName)?
-                };
                 let path = if let Some(parent) = &parent {
                     parent.push(name.clone())
                 } else {
@@ -128,10 +270,14 @@ impl RawNode {
                     name,
                     parent,
                     record_file: record_file.clone(),
+                    span: span.map(|span| span.to_span(source.text)),
                 };
+                let target = raw_node.borrow()
+                    .string_property(TARGET, span, source, &path)?
+                    .ok_or_else(|| Error::LackTarget(source.location(start, &path)))?;
                 let link_node = Rc::new(RefCell::new(Node::Link(LinkNode {
                     node_properties,
-                    target: PathBuf::from(raw_node.borrow().properties.get("target").cloned().ok_or(Error::LackTarget)?)
+                    target: PathBuf::from(target)
                 })));
                 Ok(vec![(path, link_node)])
             },
@@ -140,7 +286,7 @@ impl RawNode {
 }
 
 impl Tree {
-    /// Insert nodes from JSON string `node_str` in `recorded_file` to `tree`.
+    /// Insert nodes from string `node_str` in `recorded_file` to `tree`. The format of `node_str` is chosen by the extension of `recorded_file`.
     ///
     /// If `node_str` is the root of Wispha tree, `parent_and_given_name` should be `None`;
     /// else `parent` should be the `node_str`'s parent, `given_name` should be the link node's `name`
@@ -149,18 +295,45 @@ impl Tree {
                                  recorded_file: PathBuf,
                                  parent_and_given_name: Option<(NodePath, String)>,
                                  preserved_keys: &Vec<&'static str>) -> Result<Rc<RefCell<Node>>, Error> {
-        let raw_node = serde_json::from_str::<RawNode>(node_str).map_err(|error| Error::ParsingFailed(error))?;
-        let raw_node = Rc::new(RefCell::new(raw_node));
+        let format = format::format_of(&recorded_file);
+        self.insert_nodes_from_str_in_format(node_str, recorded_file, parent_and_given_name, preserved_keys, format)
+    }
+
+    /// Insert nodes from string `node_str` in `format`, like `insert_nodes_from_str`
+    pub fn insert_nodes_from_str_in_format(&self,
+                                           node_str: &str,
+                                           recorded_file: PathBuf,
+                                           parent_and_given_name: Option<(NodePath, String)>,
+                                           preserved_keys: &Vec<&'static str>,
+                                           format: &dyn Format) -> Result<Rc<RefCell<Node>>, Error> {
+        let source = Source {
+            record_file: &recorded_file,
+            text: node_str
+        };
         let (parent, given_name) = if let Some((parent, given_name)) = parent_and_given_name {
             (Some(parent), given_name)
         } else {
             (None, self.config().project_name.clone())
         };
+        let node_path = if let Some(parent) = &parent {
+            parent.push(given_name.clone())
+        } else {
+            NodePath::new(self)
+        };
+        let value = format.parse(node_str).map_err(|parse_error| {
+            let (line, column) = parse_error.position.unwrap_or((1, 1));
+            let location = source.location_at(line, column, &node_path);
+            Error::ParsingFailed((parse_error.error, location))
+        })?;
+        let span = format.scan(node_str);
+        let raw_node = RawNode::from_value(value, span.as_ref(), &source, &node_path)?;
+        let raw_node = Rc::new(RefCell::new(raw_node));
         let nodes = RawNode::convert_to_nodes(&raw_node,
+                                              span.as_ref(),
                                               parent,
                                               Some(given_name),
-                                              &self,
-                                              &recorded_file,
+                                              self,
+                                              &source,
                                               preserved_keys)?;
         let root = Rc::clone(&nodes.last().unwrap().1);
         for (path, node) in nodes {
@@ -170,37 +343,68 @@ impl Tree {
     }
 }
 
+/// Errors of deserialization. Each error is located in its record file
 #[derive(Debug)]
 pub enum Error {
-    ParsingFailed(serde_json::error::Error),
+    ParsingFailed((Box<dyn error::Error>, Location)),
+    /// A node is not an object
+    NotObject(Location),
+    /// The value of a key like `children` is not an array
+    NotArray((String, Location)),
     /// Type of node is unknown
-    UnknownType(String),
+    UnknownType((String, Location)),
     /// A node which is not the upmost node in a file, has no name
-    LackName,
+    LackName(Location),
     /// A node whose type is "Link" and lacks a target
-    LackTarget,
+    LackTarget(Location),
     /// Key is preserved
-    PreservedKey(String),
+    PreservedKey((String, Location)),
+    /// The value of a key like `name`, `type` or `target` is not a string
+    NotString((String, Location)),
 }
 
-impl error::Error for Error {}
+impl Error {
+    /// The error message without location
+    pub fn message(&self) -> String {
+        use Error::*;
+        match &self {
+            ParsingFailed((error, _)) => format!("Syntax parsing error: {}", error),
+            NotObject(_) => String::from("A node should be an object."),
+            NotArray((key, _)) => format!("Value of key {} should be an array.", key),
+            UnknownType((type_str, _)) => format!("Unknown type {}", type_str),
+            LackName(_) => String::from("Lack name"),
+            LackTarget(_) => String::from(r#"The node whose type is "Link" lacks target"#),
+            PreservedKey((key, _)) => format!("Key {} is preserved.", key),
+            NotString((key, _)) => format!("Value of key {} should be a string.", key),
+        }
+    }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    /// Where the error occurs
+    pub fn location(&self) -> &Location {
         use Error::*;
-        let message = match &self {
-            ParsingFailed(error) => format!("JSON syntax parsing error: {}", error),
-            UnknownType(type_str) => format!("Unknown type {}", type_str),
-            LackName => String::from("Lack name"),
-            LackTarget => String::from(r#"The node whose type is "Link" lacks target"#),
-            PreservedKey(key) => format!("Key {} is preserved.", key),
-        };
-        write!(f, "{}", message)
+        match &self {
+            ParsingFailed((_, location)) => location,
+            NotObject(location) => location,
+            NotArray((_, location)) => location,
+            UnknownType((_, location)) => location,
+            LackName(location) => location,
+            LackTarget(location) => location,
+            PreservedKey((_, location)) => location,
+            NotString((_, location)) => location,
+        }
     }
 }
 
-impl From<serde_json::error::Error> for Error {
-    fn from(error: serde_json::error::Error) -> Self {
-        Error::ParsingFailed(error)
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let location = self.location();
+        write!(f, "{}:{}:{}: {} (node {})",
+               location.record_file.to_string_lossy(),
+               location.line,
+               location.column,
+               self.message(),
+               location.node_path)
     }
 }
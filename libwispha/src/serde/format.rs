@@ -0,0 +1,198 @@
+//! Formats of record files. The format of a record file is chosen by its extension,
+//! so files of different formats can be linked in one Wispha tree.
+
+use crate::core::*;
+use crate::serde::span::{self, ObjectSpan};
+use crate::serde::syntax;
+
+use serde::Serialize;
+
+use std::error;
+use std::path::Path;
+
+/// A format that record files are written in.
+///
+/// A record file is parsed into a `PropertyValue`, whose keys `name`, `type`, `target` and `children`
+/// have the same meaning in every format.
+pub trait Format {
+    /// Name of the format, like "json"
+    fn name(&self) -> &'static str;
+
+    /// Extensions of record files in this format, without the leading `.`. The first one is used for new files
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Parse the content of a record file
+    fn parse(&self, text: &str) -> Result<PropertyValue, ParseError>;
+
+    /// Write `value` as the content of a record file
+    fn write(&self, value: &PropertyValue) -> Result<String, Box<dyn error::Error>>;
+
+    /// Write `value` in place of `text`, the existing content of a record file.
+    /// Formats that can edit text in place keep its comments and formatting; by default `value` is written afresh
+    fn rewrite(&self, _text: &str, value: &PropertyValue) -> Result<String, Box<dyn error::Error>> {
+        self.write(value)
+    }
+
+    /// Locate the upmost node and its descendants in `text`, which are used for node spans and error locations.
+    /// `None` by default, which means nodes are not located
+    fn scan(&self, _text: &str) -> Option<ObjectSpan> {
+        None
+    }
+}
+
+/// Error of parsing a record file
+#[derive(Debug)]
+pub struct ParseError {
+    pub error: Box<dyn error::Error>,
+    /// Line and column of the error, both starting from 1, if the format reports it
+    pub position: Option<(usize, usize)>,
+}
+
+impl ParseError {
+    fn new(error: impl error::Error + 'static, position: Option<(usize, usize)>) -> ParseError {
+        ParseError {
+            error: Box::new(error),
+            position
+        }
+    }
+}
+
+/// JSON format, for `.json` files. JSON5 syntax is accepted as well, like comments and trailing commas,
+/// which are kept when a file is rewritten. Values are written in plain JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+/// TOML format, for `.toml` files. Children are written as an array of tables
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Toml;
+
+/// YAML format, for `.yaml` and `.yml` files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yaml;
+
+impl Format for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json", "jsonc", "json5"]
+    }
+
+    fn parse(&self, text: &str) -> Result<PropertyValue, ParseError> {
+        let json_error = match serde_json::from_str(text) {
+            Ok(value) => return Ok(value),
+            Err(json_error) => json_error
+        };
+        let json5_error = match json5::from_str(text) {
+            Ok(value) => return Ok(value),
+            Err(json5_error) => json5_error
+        };
+        // Read as JSONC, whose errors are clearer than those of JSON5
+        let json_error = serde_json::from_str::<PropertyValue>(&syntax::blank_comments(text)).err().unwrap_or(json_error);
+        // `serde_json` reports column 0 at the start of a line
+        let json_position = (json_error.line().max(1), json_error.column().max(1));
+        let json5::Error::Message { location, .. } = &json5_error;
+        let json5_position = location.as_ref().map(|location| (location.line, location.column));
+        // The error found further in the text is reported, since the text before it is valid in either syntax
+        match json5_position {
+            Some(json5_position) if json5_position > json_position => Err(ParseError::new(json5_error, Some(json5_position))),
+            _ => Err(ParseError::new(json_error, Some(json_position)))
+        }
+    }
+
+    fn write(&self, value: &PropertyValue) -> Result<String, Box<dyn error::Error>> {
+        Ok(serde_json::to_string_pretty(value)?)
+    }
+
+    fn rewrite(&self, text: &str, value: &PropertyValue) -> Result<String, Box<dyn error::Error>> {
+        let old = self.parse(text).map_err(|parse_error| parse_error.error)?;
+        if let Some(content) = syntax::rewrite(text, &old, value) {
+            Ok(content)
+        } else {
+            self.write(value)
+        }
+    }
+
+    fn scan(&self, text: &str) -> Option<ObjectSpan> {
+        span::scan(text)
+    }
+}
+
+impl Format for Toml {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["toml"]
+    }
+
+    fn parse(&self, text: &str) -> Result<PropertyValue, ParseError> {
+        toml::from_str(text).map_err(|error| {
+            let position = error.span().map(|range| span::line_column(text, range.start));
+            ParseError::new(error, position)
+        })
+    }
+
+    fn write(&self, value: &PropertyValue) -> Result<String, Box<dyn error::Error>> {
+        Ok(toml::to_string_pretty(value)?)
+    }
+
+    fn scan(&self, text: &str) -> Option<ObjectSpan> {
+        span::scan_toml(text)
+    }
+}
+
+impl Format for Yaml {
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["yaml", "yml"]
+    }
+
+    fn parse(&self, text: &str) -> Result<PropertyValue, ParseError> {
+        serde_yaml::from_str(text).map_err(|error| {
+            let position = error.location().map(|location| (location.line(), location.column()));
+            ParseError::new(error, position)
+        })
+    }
+
+    fn write(&self, value: &PropertyValue) -> Result<String, Box<dyn error::Error>> {
+        Ok(serde_yaml::to_string(value)?)
+    }
+
+    fn scan(&self, text: &str) -> Option<ObjectSpan> {
+        span::scan_yaml(text)
+    }
+}
+
+/// Formats that record files can be written in
+pub static FORMATS: [&(dyn Format + Sync); 3] = [&Json, &Toml, &Yaml];
+
+/// The format of `record_file` by its extension. Files with unknown extensions are in JSON
+pub fn format_of(record_file: &Path) -> &'static dyn Format {
+    let extension = record_file.extension().and_then(|extension| extension.to_str());
+    extension.and_then(|extension| {
+        FORMATS.iter().find(|format| format.extensions().contains(&extension))
+    }).map(|format| *format as &dyn Format).unwrap_or(&Json)
+}
+
+/// The format whose name is `name`
+pub fn format_named(name: &str) -> Option<&'static dyn Format> {
+    FORMATS.iter()
+        .find(|format| format.name() == name)
+        .map(|format| *format as &dyn Format)
+}
+
+/// Write `value`, like a `Node`, in `format`
+pub fn to_string<T: Serialize + ?Sized>(value: &T, format: &dyn Format) -> Result<String, Box<dyn error::Error>> {
+    format.write(&to_property_value(value)?)
+}
+
+/// Convert a serializable value, like a `Node`, to a `PropertyValue`. Objects keep the order of their entries
+pub fn to_property_value<T: Serialize + ?Sized>(value: &T) -> Result<PropertyValue, serde_json::Error> {
+    serde_json::from_value(serde_json::to_value(value)?)
+}
@@ -1,4 +1,7 @@
 //! Handle the serialization and deserialization of Wispha nodes.
 
 pub mod ser;
-pub mod de;
\ No newline at end of file
+pub mod de;
+pub mod format;
+pub mod span;
+mod syntax;
\ No newline at end of file
@@ -1,60 +1,324 @@
 use std::error;
 use std::fmt;
+use std::fs;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 
 use crate::core::*;
 use crate::strings::*;
+use crate::manipulator::{normalize_path, relative_path};
+use crate::serde::span::ObjectSpan;
+use crate::serde::format::{self, Format};
 
 use serde::ser::{Serializer, SerializeMap};
 use serde::Serialize;
-use serde_json;
 
-impl Serialize for DirectNode {
+// A node to be serialized, with its children in `order`
+struct OrderedNode<'a> {
+    node: &'a Node,
+    order: ChildrenOrder,
+}
+
+impl Serialize for OrderedNode<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer {
+        match self.node {
+            Node::Direct(direct_node) => serialize_direct_node(direct_node, self.order, serializer),
+            Node::Link(link_node) => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry(NAME, &link_node.node_properties.name)?;
+                map.serialize_entry(TYPE, LINK)?;
+                map.serialize_entry(TARGET, &link_node.target)?;
+                map.end()
+            }
+        }
+    }
+}
+
+// `name` comes first, then properties in their recorded order, and `children` comes last
+fn serialize_direct_node<S>(direct_node: &DirectNode, order: ChildrenOrder, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry(NAME, &direct_node.node_properties.name)?;
+    for (property, value) in direct_node.properties.iter() {
+        map.serialize_entry(property, value)?;
+    }
+    if !direct_node.children.is_empty() {
+        let mut children = direct_node.children.iter()
+                                      .map(|node_path| -> Result<_, S::Error> {
+                                          node_path.tree()
+                                                   .get_node(node_path)
+                                                   .ok_or(Error::PathNotFound(node_path.clone()))
+                                                   .map_err(serde::ser::Error::custom)
+                                      })
+                                      .collect::<Result<Vec<_>, S::Error>>()?;
+        sort_children(&mut children, order);
+        let children = children.iter().map(|child| child.borrow()).collect::<Vec<_>>();
+        let children = children.iter()
+                               .map(|child| OrderedNode { node: child, order })
+                               .collect::<Vec<_>>();
+        map.serialize_entry(CHILDREN, &children)?;
+    }
+    map.end()
+}
+
+fn sort_children(children: &mut [Rc<RefCell<Node>>], order: ChildrenOrder) {
+    let is_directory = |node: &Node| match node {
+        Node::Direct(direct_node) => !direct_node.children.is_empty(),
+        Node::Link(_) => true,
+    };
+    match order {
+        ChildrenOrder::AsAuthored => {},
+        ChildrenOrder::Alphabetical => {
+            children.sort_by_key(|child| child.borrow().node_properties().name)
+        },
+        ChildrenOrder::DirectoriesFirst => {
+            children.sort_by_key(|child| {
+                let child = child.borrow();
+                (!is_directory(&child), child.node_properties().name)
+            })
+        }
+    }
+}
+
+// A direct node to be written to `record_file`. Its descendants recorded in other files are written as link nodes.
+// `name` is only written if `name` is `Some`.
+struct RecordedNode<'a> {
+    node: &'a DirectNode,
+    record_file: &'a Path,
+    name: Option<&'a str>,
+}
+
+// A child in a record file, which is either recorded in the file or linked to another file
+enum RecordedChild<'a> {
+    Inline(RecordedNode<'a>),
+    Link((&'a str, PathBuf)),
+}
+
+impl Serialize for RecordedNode<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer {
-        // TODO: find a more graceful way
-        // WARN: THIS IS NOT PRESERVING ORDER
         let mut map = serializer.serialize_map(None)?;
-        map.serialize_entry(NAME, &self.node_properties.name)?;
-        for (property, value) in self.properties.iter() {
+        if let Some(name) = self.name {
+            map.serialize_entry(NAME, name)?;
+        }
+        for (property, value) in self.node.properties.iter() {
             map.serialize_entry(property, value)?;
         }
-        if !self.children.is_empty() {
-            let children = self.children.iter()
-                               .map(|node_path| -> Result<_, S::Error> {
-                                   Ok(node_path.tree()
-                                               .get_node(node_path)
-                                               .ok_or(Error::PathNotFound(node_path.clone()))
-                                               .map_err(serde::ser::Error::custom)?)
-                               })
-                               .collect::<Result<Vec<_>, S::Error>>()?;
+        if !self.node.children.is_empty() {
+            let children = self.node.children.iter()
+                                .map(|node_path| -> Result<_, S::Error> {
+                                    node_path.tree()
+                                             .get_node(node_path)
+                                             .ok_or(Error::PathNotFound(node_path.clone()))
+                                             .map_err(serde::ser::Error::custom)
+                                })
+                                .collect::<Result<Vec<_>, S::Error>>()?;
+            let children = children.iter().map(|child| child.borrow()).collect::<Vec<_>>();
+            let children = children.iter().map(|child| match &**child {
+                Node::Direct(direct_node) if direct_node.node_properties.record_file != self.record_file => {
+                    RecordedChild::Link((&direct_node.node_properties.name, link_target(direct_node, self.record_file)))
+                },
+                Node::Direct(direct_node) => RecordedChild::Inline(RecordedNode {
+                    node: direct_node,
+                    record_file: self.record_file,
+                    name: Some(&direct_node.node_properties.name)
+                }),
+                Node::Link(link_node) => RecordedChild::Link((&link_node.node_properties.name, link_node.target.clone())),
+            }).collect::<Vec<_>>();
             map.serialize_entry(CHILDREN, &children)?;
         }
         map.end()
     }
 }
 
-impl Serialize for InnerTree {
+impl Serialize for RecordedChild<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer {
-        if !self.nodes.is_empty() {
-            self.root().unwrap().borrow().serialize(serializer)
-        } else {
-            serializer.serialize_none()
+        match self {
+            RecordedChild::Inline(recorded_node) => recorded_node.serialize(serializer),
+            RecordedChild::Link((name, target)) => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry(NAME, name)?;
+                map.serialize_entry(TYPE, LINK)?;
+                map.serialize_entry(TARGET, target)?;
+                map.end()
+            }
         }
     }
 }
 
+// The target of a link in `record_file` to `direct_node`.
+// The recorded target is kept if it still points to the file `direct_node` is recorded in.
+fn link_target(direct_node: &DirectNode, record_file: &Path) -> PathBuf {
+    let dir = record_file.parent().unwrap();
+    let target_file = &direct_node.node_properties.record_file;
+    if let Some(target) = &direct_node.link_target {
+        if normalize_path(&dir.join(target)) == normalize_path(target_file) {
+            return target.clone();
+        }
+    }
+    relative_path(dir, target_file)
+}
+
+impl Serialize for Node {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer {
+        OrderedNode { node: self, order: ChildrenOrder::AsAuthored }.serialize(serializer)
+    }
+}
+
+impl Serialize for DirectNode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer {
+        serialize_direct_node(self, ChildrenOrder::AsAuthored, serializer)
+    }
+}
+
 impl Tree {
-    /// Convert tree to JSON syntax
+    /// Convert tree to JSON syntax, with children in the order they are recorded
     pub fn to_string(&self) -> Result<String, Error> {
-        if self.0.borrow().nodes.is_empty() {
-            Err(Error::EmptyTree)
+        self.to_string_with_order(ChildrenOrder::AsAuthored)
+    }
+
+    /// Convert tree to JSON syntax, with children of every node in `order`
+    pub fn to_string_with_order(&self, order: ChildrenOrder) -> Result<String, Error> {
+        let root = self.root().ok_or(Error::EmptyTree)?;
+        let root = root.borrow();
+        serde_json::to_string(&OrderedNode { node: &root, order })
+            .map_err(|error| Error::SerializeFailed(Box::new(error)))
+    }
+
+    /// Convert tree to `format`, with children of every node in `order`
+    pub fn to_string_in_format(&self, order: ChildrenOrder, format: &dyn Format) -> Result<String, Error> {
+        let root = self.root().ok_or(Error::EmptyTree)?;
+        let root = root.borrow();
+        format::to_string(&OrderedNode { node: &root, order }, format)
+            .map_err(Error::SerializeFailed)
+    }
+
+    /// Write the tree back to the files its nodes are recorded in, and return the files that are written.
+    ///
+    /// Nodes are grouped by their record files. In each file, nodes recorded in other files are written as link nodes,
+    /// and unresolved link nodes are kept as they are. A file is only written if its content changes.
+    /// If a file is linked more than once, it is written from the first node recorded in it, in breadth-first order.
+    /// Each file is written in the format of its extension, keeping its comments and formatting if the format can.
+    ///
+    /// The spans of nodes recorded in written files are updated to their new places.
+    pub fn save(&self) -> Result<Vec<PathBuf>, Error> {
+        if self.root().is_none() {
+            return Err(Error::EmptyTree);
+        }
+        let mut written_files = vec![];
+        // the upmost node and the new content of each written file
+        let mut written_contents = vec![];
+        let mut saved_files = HashSet::new();
+        let mut path_queue = VecDeque::new();
+        path_queue.push_back(NodePath::new(self));
+        while let Some(node_path) = path_queue.pop_front() {
+            let node = self.get_node(&node_path).ok_or(Error::PathNotFound(node_path.clone()))?;
+            let node = node.borrow();
+            let direct_node = if let Node::Direct(direct_node) = &*node {
+                direct_node
+            } else {
+                continue;
+            };
+            let record_file = &direct_node.node_properties.record_file;
+            let is_top_of_file = if let Some(parent) = &direct_node.node_properties.parent {
+                self.get_node(parent)
+                    .map(|parent| &parent.borrow().node_properties().record_file != record_file)
+                    .unwrap_or(true)
+            } else {
+                true
+            };
+            if is_top_of_file && saved_files.insert(record_file.clone()) {
+                if let Some(content) = save_file(direct_node, record_file)? {
+                    written_files.push(record_file.clone());
+                    written_contents.push((node_path.clone(), record_file.clone(), content));
+                }
+            }
+            for child in &direct_node.children {
+                path_queue.push_back(child.clone());
+            }
+        }
+        for (node_path, record_file, content) in written_contents {
+            if let Some(object_span) = format::format_of(&record_file).scan(&content) {
+                self.update_spans(&node_path, &object_span, &content);
+            }
+        }
+        Ok(written_files)
+    }
+
+    // Set the span of the node at `node_path` and its descendants recorded in the same file, which is written as `content`
+    fn update_spans(&self, node_path: &NodePath, object_span: &ObjectSpan, content: &str) {
+        let node = if let Some(node) = self.get_node(node_path) {
+            node
         } else {
-            serde_json::to_string(&*self.0.borrow()).map_err(|error| Error::SerializeFailed(Box::new(error)))
+            return;
+        };
+        let (record_file, children) = {
+            let mut node = node.borrow_mut();
+            node.node_properties_mut().span = Some(object_span.to_span(content));
+            let record_file = node.node_properties().record_file;
+            let children = node.get_direct().map(|direct_node| direct_node.children.clone()).unwrap_or_default();
+            (record_file, children)
+        };
+        // Children are written in order, either inline or as links
+        for (child, child_span) in children.iter().zip(&object_span.children) {
+            let is_in_file = self.get_node(child)
+                .map(|child| child.borrow().node_properties().record_file == record_file)
+                .unwrap_or(false);
+            if is_in_file {
+                self.update_spans(child, child_span, content);
+            }
+        }
+    }
+}
+
+// Write `direct_node` to `record_file` if the content changes, and return the content if the file is written.
+// Since the name of the upmost node in a file is given by its parent, the recorded name is kept as it is.
+fn save_file(direct_node: &DirectNode, record_file: &Path) -> Result<Option<String>, Error> {
+    let format = format::format_of(record_file);
+    let existing_content = fs::read_to_string(record_file).ok();
+    let existing = existing_content.as_ref()
+        .and_then(|content| format.parse(content).ok());
+    let name = if let Some(existing) = &existing {
+        match existing {
+            PropertyValue::Object(existing) => match existing.get(NAME) {
+                Some(PropertyValue::String(name)) => Some(name.as_str()),
+                _ => None
+            },
+            _ => None
         }
+    } else {
+        Some(direct_node.node_properties.name.as_str())
+    };
+    let recorded_node = RecordedNode {
+        node: direct_node,
+        record_file,
+        name
+    };
+    let value = format::to_property_value(&recorded_node)
+        .map_err(|error| Error::SerializeFailed(Box::new(error)))?;
+    if existing.as_ref() == Some(&value) {
+        return Ok(None);
     }
+    // Comments and formatting of the existing file are kept if the format can
+    let content = match (&existing_content, &existing) {
+        (Some(existing_content), Some(_)) => format.rewrite(existing_content, &value),
+        _ => format.write(&value)
+    }.map_err(Error::SerializeFailed)?;
+    fs::write(record_file, &content)
+        .map_err(|io_error| Error::CannotWrite((record_file.to_path_buf(), io_error)))?;
+    Ok(Some(content))
 }
 
 #[derive(Debug)]
@@ -62,7 +326,8 @@ pub enum Error {
     PathNotFound(NodePath),
     SerializeFailed(Box<dyn error::Error>),
     /// Tree has no nodes
-    EmptyTree
+    EmptyTree,
+    CannotWrite((PathBuf, std::io::Error)),
 }
 
 impl error::Error for Error {}
@@ -73,7 +338,8 @@ impl fmt::Display for Error {
         let message = match &self {
             PathNotFound(path) => format!("Path {} not found.", path),
             SerializeFailed(error) => format!("Serialize error: {}", error),
-            EmptyTree => String::from("Tree is empty.")
+            EmptyTree => String::from("Tree is empty."),
+            CannotWrite((path, io_error)) => format!("Cannot write to {}: {}", path.to_str().unwrap(), io_error),
         };
         write!(f, "{}", message)
     }
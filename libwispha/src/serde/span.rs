@@ -0,0 +1,231 @@
+//! Locate the objects of Wispha nodes in a record file, which `serde_json`, `toml` and `serde_yaml` do not report.
+
+use crate::core::Span;
+use crate::strings::*;
+use crate::serde::syntax::{self, Kind, Value};
+
+use indexmap::IndexMap;
+use toml_edit::{ImDocument, Item, TableLike};
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+use std::ops::Range;
+
+/// Byte offsets of an object recorded in a file
+#[derive(Debug, Default, Clone)]
+pub struct ObjectSpan {
+    /// Offset of the start of the object, which is `{` in JSON, the table header in TOML, or the first key in YAML
+    pub start: usize,
+    /// Offset after the object
+    pub end: usize,
+    /// Offset of each key, which is its opening quote if it's quoted
+    pub keys: IndexMap<String, usize>,
+    /// Spans of the objects in `children`, in order
+    pub children: Vec<ObjectSpan>,
+}
+
+/// Scan the upmost object of `text`, which may be JSON5 with comments and trailing commas.
+/// `text` should be valid, or `None` may be returned
+pub fn scan(text: &str) -> Option<ObjectSpan> {
+    object_span(&syntax::parse(text)?)
+}
+
+fn object_span(value: &Value) -> Option<ObjectSpan> {
+    let container = if let Kind::Object(container) = &value.kind {
+        container
+    } else {
+        return None;
+    };
+    let mut span = ObjectSpan {
+        start: value.start,
+        end: value.end,
+        ..ObjectSpan::default()
+    };
+    for item in &container.items {
+        let key = item.key.clone()?;
+        if key == CHILDREN {
+            if let Kind::Array(children) = &item.value.kind {
+                span.children = children.items.iter()
+                    .map(|child| object_span(&child.value))
+                    .collect::<Option<Vec<_>>>()?;
+            }
+        }
+        span.keys.insert(key, item.start);
+    }
+    Some(span)
+}
+
+/// Scan the upmost table of TOML `text`.
+/// `text` should be valid, or `None` may be returned
+pub fn scan_toml(text: &str) -> Option<ObjectSpan> {
+    let document = ImDocument::parse(text).ok()?;
+    table_span(document.as_table(), None)
+}
+
+// The span of a TOML table, whose own span is `range` if known. A standard table only spans its header,
+// so the table ends after its last key, value or sub-table
+fn table_span(table: &dyn TableLike, range: Option<Range<usize>>) -> Option<ObjectSpan> {
+    let mut span = ObjectSpan::default();
+    let mut start = range.as_ref().map(|range| range.start);
+    let mut end = range.as_ref().map(|range| range.end).unwrap_or(0);
+    for (key, item) in table.iter() {
+        if let Some(key_range) = table.get_key_value(key).and_then(|(key, _)| key.span()) {
+            start = Some(start.map(|start| start.min(key_range.start)).unwrap_or(key_range.start));
+            end = end.max(key_range.end);
+            span.keys.insert(key.to_string(), key_range.start);
+        }
+        let item_spans = match item {
+            Item::ArrayOfTables(tables) => tables.iter()
+                .map(|table| table_span(table, table.span()))
+                .collect::<Option<Vec<_>>>()?,
+            // Children are only located if all of them are tables
+            Item::Value(toml_edit::Value::Array(values)) => values.iter()
+                .map(|value| value.as_inline_table().and_then(|table| table_span(table, table.span())))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default(),
+            Item::Table(sub_table) => vec![table_span(sub_table, sub_table.span())?],
+            Item::Value(toml_edit::Value::InlineTable(sub_table)) => vec![table_span(sub_table, sub_table.span())?],
+            _ => vec![]
+        };
+        end = end.max(item.span().map(|range| range.end).unwrap_or(0));
+        end = item_spans.iter().fold(end, |end, item_span| end.max(item_span.end));
+        if key == CHILDREN {
+            span.children = item_spans;
+        }
+    }
+    span.start = start.unwrap_or(0);
+    span.end = end.max(span.start + 1);
+    Some(span)
+}
+
+/// Scan the upmost mapping of YAML `text`.
+/// `text` should be valid, or `None` may be returned
+pub fn scan_yaml(text: &str) -> Option<ObjectSpan> {
+    let mut receiver = YamlReceiver {
+        // YAML markers count characters, which are converted to byte offsets
+        offsets: text.char_indices().map(|(offset, _)| offset).chain(std::iter::once(text.len())).collect(),
+        text,
+        stack: vec![],
+        root: None
+    };
+    Parser::new_from_str(text).load(&mut receiver, false).ok()?;
+    match receiver.root? {
+        YamlNode::Mapping(span) => Some(span),
+        _ => None
+    }
+}
+
+enum YamlNode {
+    Scalar((String, usize)),
+    Mapping(ObjectSpan),
+    Sequence(Vec<YamlNode>),
+}
+
+// A mapping or sequence being received
+enum YamlContainer {
+    Mapping {
+        span: ObjectSpan,
+        /// The key and its offset, if its value is not received yet
+        key: Option<(String, usize)>,
+    },
+    Sequence(Vec<YamlNode>),
+}
+
+struct YamlReceiver<'a> {
+    offsets: Vec<usize>,
+    text: &'a str,
+    stack: Vec<YamlContainer>,
+    root: Option<YamlNode>,
+}
+
+impl YamlReceiver<'_> {
+    fn offset(&self, mark: &Marker) -> usize {
+        self.offsets.get(mark.index()).copied().unwrap_or(self.text.len())
+    }
+
+    fn push_node(&mut self, node: YamlNode) {
+        match self.stack.last_mut() {
+            Some(YamlContainer::Mapping { span, key }) => {
+                if let Some((key, key_offset)) = key.take() {
+                    if key == CHILDREN {
+                        if let YamlNode::Sequence(children) = &node {
+                            // Children are only located if all of them are mappings
+                            span.children = children.iter().map(|child| match child {
+                                YamlNode::Mapping(child_span) => Some(child_span.clone()),
+                                _ => None
+                            }).collect::<Option<Vec<_>>>().unwrap_or_default();
+                        }
+                    }
+                    span.keys.insert(key, key_offset);
+                } else if let YamlNode::Scalar((key_name, key_offset)) = node {
+                    // A block mapping starts at its first key, which is only known here
+                    if span.keys.is_empty() {
+                        span.start = span.start.min(key_offset);
+                    }
+                    *key = Some((key_name, key_offset));
+                }
+            },
+            Some(YamlContainer::Sequence(items)) => items.push(node),
+            None => self.root = Some(node)
+        }
+    }
+}
+
+impl MarkedEventReceiver for YamlReceiver<'_> {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let offset = self.offset(&mark);
+        match event {
+            Event::Scalar(value, ..) => self.push_node(YamlNode::Scalar((value, offset))),
+            Event::Alias(_) => self.push_node(YamlNode::Scalar((String::new(), offset))),
+            Event::MappingStart(..) => self.stack.push(YamlContainer::Mapping {
+                span: ObjectSpan {
+                    start: offset,
+                    ..ObjectSpan::default()
+                },
+                key: None
+            }),
+            Event::SequenceStart(..) => self.stack.push(YamlContainer::Sequence(vec![])),
+            Event::MappingEnd => {
+                if let Some(YamlContainer::Mapping { mut span, .. }) = self.stack.pop() {
+                    // A flow mapping ends at `}`, and a block mapping ends where the next token starts
+                    span.end = if self.text[offset..].starts_with('}') {
+                        offset + 1
+                    } else {
+                        self.text[..offset].trim_end().len()
+                    }.max(span.start + 1);
+                    self.push_node(YamlNode::Mapping(span));
+                }
+            },
+            Event::SequenceEnd => {
+                if let Some(YamlContainer::Sequence(items)) = self.stack.pop() {
+                    self.push_node(YamlNode::Sequence(items));
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Line and column of byte `offset` in `text`, both starting from 1. Columns are counted in characters
+pub(crate) fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+impl ObjectSpan {
+    pub(crate) fn to_span(&self, text: &str) -> Span {
+        let (start_line, start_column) = line_column(text, self.start);
+        let (end_line, end_column) = line_column(text, self.end - 1);
+        Span {
+            start: self.start,
+            end: self.end,
+            start_line,
+            start_column,
+            end_line,
+            end_column
+        }
+    }
+}
@@ -0,0 +1,430 @@
+//! Syntax of JSON and JSON5 text, with comments and trailing commas.
+//!
+//! Values are only located rather than decoded, so that a record file can be edited in place,
+//! keeping the comments and formatting of the parts that do not change.
+
+use crate::core::PropertyValue;
+use crate::strings::*;
+
+use serde::Serialize;
+
+/// A value located in text
+pub(crate) struct Value {
+    /// Offset of the value's first byte
+    pub(crate) start: usize,
+    /// Offset after the value's last byte
+    pub(crate) end: usize,
+    pub(crate) kind: Kind,
+}
+
+pub(crate) enum Kind {
+    Object(Container),
+    Array(Container),
+    /// A string, number, `true`, `false` or `null`
+    Scalar,
+}
+
+/// Items of an object or array
+pub(crate) struct Container {
+    pub(crate) items: Vec<Item>,
+    /// Whether the last item is followed by a comma
+    trailing_comma: bool,
+    /// Offset after the last item, or after its trailing comma. The text from here to the closing bracket is kept as it is
+    tail_start: usize,
+}
+
+/// An entry of an object, or an element of an array
+pub(crate) struct Item {
+    /// Offset after the opening bracket or the previous comma, where the whitespace and comments before the item start
+    lead_start: usize,
+    /// Offset of the key, or of the value for an element of an array
+    pub(crate) start: usize,
+    /// Unquoted key of an entry
+    pub(crate) key: Option<String>,
+    pub(crate) value: Value,
+    /// Offset of the comma after the item, or the end of the value if no comma follows
+    trail_end: usize,
+}
+
+/// Parse `text`, which should be a single value. `None` is returned if `text` is not valid JSON5
+pub(crate) fn parse(text: &str) -> Option<Value> {
+    let mut parser = Parser {
+        text,
+        bytes: text.as_bytes(),
+        pos: 0
+    };
+    parser.skip_trivia()?;
+    let value = parser.value()?;
+    parser.skip_trivia()?;
+    if parser.pos == text.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    // Skip whitespace and comments. `None` is returned if a block comment is not closed
+    fn skip_trivia(&mut self) -> Option<()> {
+        loop {
+            match self.peek() {
+                Some(byte) if byte.is_ascii_whitespace() => self.pos += 1,
+                Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                    self.pos = self.text[self.pos..].find('\n').map(|index| self.pos + index + 1).unwrap_or(self.text.len());
+                },
+                Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'*') => {
+                    self.pos = self.pos + 2 + self.text[self.pos + 2..].find("*/")? + 2;
+                },
+                _ => return Some(())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        let start = self.pos;
+        let kind = match self.peek()? {
+            b'{' => Kind::Object(self.container(b'}', true)?),
+            b'[' => Kind::Array(self.container(b']', false)?),
+            b'"' | b'\'' => {
+                self.string()?;
+                Kind::Scalar
+            },
+            _ => {
+                self.bare_word()?;
+                Kind::Scalar
+            }
+        };
+        Some(Value {
+            start,
+            end: self.pos,
+            kind
+        })
+    }
+
+    // Items between the opening bracket under the cursor and `close`
+    fn container(&mut self, close: u8, is_object: bool) -> Option<Container> {
+        self.pos += 1;
+        let mut items = vec![];
+        loop {
+            let lead_start = self.pos;
+            self.skip_trivia()?;
+            if self.peek()? == close {
+                // Either empty, or the last item is followed by a comma
+                self.pos += 1;
+                return Some(Container {
+                    trailing_comma: !items.is_empty(),
+                    items,
+                    tail_start: lead_start
+                });
+            }
+            let start = self.pos;
+            let key = if is_object {
+                let key = self.key()?;
+                self.skip_trivia()?;
+                if self.peek()? != b':' {
+                    return None;
+                }
+                self.pos += 1;
+                self.skip_trivia()?;
+                Some(key)
+            } else {
+                None
+            };
+            let value = self.value()?;
+            self.skip_trivia()?;
+            match self.peek()? {
+                b',' => {
+                    items.push(Item {
+                        lead_start,
+                        start,
+                        key,
+                        trail_end: self.pos,
+                        value
+                    });
+                    self.pos += 1;
+                },
+                byte if byte == close => {
+                    let value_end = value.end;
+                    items.push(Item {
+                        lead_start,
+                        start,
+                        key,
+                        trail_end: value_end,
+                        value
+                    });
+                    self.pos += 1;
+                    return Some(Container {
+                        items,
+                        trailing_comma: false,
+                        tail_start: value_end
+                    });
+                },
+                _ => return None
+            }
+        }
+    }
+
+    // A quoted string or an identifier
+    fn key(&mut self) -> Option<String> {
+        match self.peek()? {
+            b'"' | b'\'' => self.string(),
+            _ => {
+                let start = self.pos;
+                self.bare_word()?;
+                Some(self.text[start..self.pos].to_string())
+            }
+        }
+    }
+
+    // The unescaped content of a string quoted by `"` or `'`
+    fn string(&mut self) -> Option<String> {
+        let start = self.pos;
+        let quote = self.peek()?;
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                byte if byte == quote => {
+                    self.pos += 1;
+                    break;
+                },
+                _ => self.pos += 1
+            }
+        }
+        json5::from_str(self.text.get(start..self.pos)?).ok()
+    }
+
+    // A number, literal or identifier, which ends before a delimiter
+    fn bare_word(&mut self) -> Option<()> {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if byte.is_ascii_whitespace() || b",:{}[]/\"'".contains(&byte) {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.pos > start {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+/// `text` with comments and trailing commas replaced by spaces, so that JSONC text can be read as JSON.
+/// Lines and columns are the same as in `text`
+pub(crate) fn blank_comments(text: &str) -> String {
+    let mut chars = text.chars().collect::<Vec<_>>();
+    // Commas outside strings, which are trailing if only whitespace follows before a closing bracket
+    let mut commas = vec![];
+    let mut quote = None;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+        match (quote, c) {
+            (Some(_), '\\') => index += 1,
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {},
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, ',') => commas.push(index),
+            (None, '/') if next == Some('/') || next == Some('*') => {
+                let is_line = next == Some('/');
+                let mut end = index + 2;
+                while end < chars.len() {
+                    if is_line && chars[end] == '\n' {
+                        break;
+                    }
+                    if !is_line && chars[end - 1] == '*' && chars[end] == '/' && end > index + 2 {
+                        end += 1;
+                        break;
+                    }
+                    end += 1;
+                }
+                let end = end.min(chars.len());
+                for c in &mut chars[index..end] {
+                    if *c != '\n' && *c != '\r' {
+                        *c = ' ';
+                    }
+                }
+                index = end;
+                continue;
+            },
+            _ => {}
+        }
+        index += 1;
+    }
+    for comma in commas {
+        if let Some('}') | Some(']') = chars[comma + 1..].iter().find(|c| !c.is_whitespace()) {
+            chars[comma] = ' ';
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Rewrite `text`, whose value is `old`, to have value `new`.
+///
+/// Only the changed parts are rewritten, so comments and formatting elsewhere are kept.
+/// Entries of objects are matched by key, and nodes in `children` are matched by name.
+/// `None` is returned if `text` can't be parsed
+pub(crate) fn rewrite(text: &str, old: &PropertyValue, new: &PropertyValue) -> Option<String> {
+    let value = parse(text)?;
+    let unit = indent_unit(text, &value);
+    let editor = Editor {
+        text,
+        unit: &unit
+    };
+    Some(format!("{}{}{}", &text[..value.start], editor.edit(&value, old, new), &text[value.end..]))
+}
+
+// The indentation of one level, which is the difference between the indentation of the upmost value and its first item.
+// Two spaces if the items are not on their own lines
+fn indent_unit(text: &str, value: &Value) -> String {
+    let first = match &value.kind {
+        Kind::Object(container) | Kind::Array(container) => container.items.first(),
+        Kind::Scalar => None
+    };
+    if let Some(first) = first {
+        let lead = &text[first.lead_start..first.start];
+        if let Some(indent) = lead.rfind('\n').map(|index| &lead[index + 1..]) {
+            if let Some(unit) = indent.strip_prefix(line_indent(text, value.start)) {
+                if !unit.is_empty() && unit.trim().is_empty() {
+                    return unit.to_string();
+                }
+            }
+        }
+    }
+    String::from("  ")
+}
+
+// Whitespace at the start of the line where `offset` is
+fn line_indent(text: &str, offset: usize) -> &str {
+    let line_start = text[..offset].rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+struct Editor<'a> {
+    text: &'a str,
+    unit: &'a str,
+}
+
+impl<'a> Editor<'a> {
+    fn edit(&self, value: &Value, old: &PropertyValue, new: &PropertyValue) -> String {
+        if old == new {
+            return self.text[value.start..value.end].to_string();
+        }
+        match (&value.kind, old, new) {
+            (Kind::Object(container), PropertyValue::Object(old_map), PropertyValue::Object(new_map))
+            if container.items.len() == old_map.len() && !container.items.is_empty() && !new_map.is_empty() => {
+                let old_items = old_map.iter().map(|(key, value)| (key.clone(), value)).collect::<Vec<_>>();
+                let new_items = new_map.iter().map(|(key, value)| (key.clone(), value)).collect::<Vec<_>>();
+                self.edit_container(value, container, &old_items, &new_items, true)
+            },
+            (Kind::Array(container), PropertyValue::Array(old_array), PropertyValue::Array(new_array))
+            if container.items.len() == old_array.len() && !container.items.is_empty() && !new_array.is_empty() => {
+                let old_items = old_array.iter().enumerate().map(|(index, value)| (element_key(index, value), value)).collect::<Vec<_>>();
+                let new_items = new_array.iter().enumerate().map(|(index, value)| (element_key(index, value), value)).collect::<Vec<_>>();
+                self.edit_container(value, container, &old_items, &new_items, false)
+            },
+            _ => render(new, line_indent(self.text, value.start), self.unit)
+        }
+    }
+
+    // Items in the order of `new_items`. Matched items keep their text, including the comments before them
+    fn edit_container(&self,
+                      value: &Value,
+                      container: &Container,
+                      old_items: &[(String, &PropertyValue)],
+                      new_items: &[(String, &PropertyValue)],
+                      is_object: bool) -> String {
+        let text = self.text;
+        let last_index = container.items.len() - 1;
+        let last = &container.items[last_index];
+
+        // New items are placed on their own lines if the last item is
+        let last_lead = &text[last.lead_start..last.start];
+        let (new_lead, new_indent) = if let Some(index) = last_lead.rfind('\n') {
+            let indent = &last_lead[index + 1..];
+            (format!("\n{}", indent), indent.to_string())
+        } else {
+            (String::from(" "), line_indent(text, last.start).to_string())
+        };
+
+        // Comments on the line of the last item stay with it, if it's followed by a comma after editing
+        let tail = &text[container.tail_start..value.end - 1];
+        let same_line = if container.trailing_comma {
+            ""
+        } else {
+            &tail[..tail.find('\n').unwrap_or(tail.len())]
+        };
+        let mut tail_rest = tail;
+
+        let mut used = vec![false; old_items.len()];
+        let mut result = text[value.start..value.start + 1].to_string();
+        for (index, (key, new_value)) in new_items.iter().enumerate() {
+            let is_last = index == new_items.len() - 1;
+            let matched = old_items.iter()
+                .enumerate()
+                .position(|(old_index, (old_key, _))| !used[old_index] && old_key == key);
+            if let Some(old_index) = matched {
+                used[old_index] = true;
+                let item = &container.items[old_index];
+                result.push_str(&text[item.lead_start..item.value.start]);
+                result.push_str(&self.edit(&item.value, old_items[old_index].1, new_value));
+                result.push_str(&text[item.value.end..item.trail_end]);
+                if old_index == last_index && !is_last {
+                    result.push(',');
+                    result.push_str(same_line);
+                    tail_rest = &tail[same_line.len()..];
+                    continue;
+                }
+            } else {
+                result.push_str(&new_lead);
+                if is_object {
+                    result.push_str(&serde_json::to_string(key).unwrap());
+                    result.push_str(": ");
+                }
+                result.push_str(&render(new_value, &new_indent, self.unit));
+            }
+            if !is_last || container.trailing_comma {
+                result.push(',');
+            }
+        }
+        if !used[last_index] {
+            tail_rest = &tail[same_line.len()..];
+        }
+        result.push_str(tail_rest);
+        result.push_str(&text[value.end - 1..value.end]);
+        result
+    }
+}
+
+// Nodes in `children` are matched by name, and other elements by index
+fn element_key(index: usize, value: &PropertyValue) -> String {
+    if let PropertyValue::Object(map) = value {
+        if let Some(PropertyValue::String(name)) = map.get(NAME) {
+            return format!("{}{}", NAME, name);
+        }
+    }
+    index.to_string()
+}
+
+// `value` in pretty JSON, which starts on a line indented by `indent`
+fn render(value: &PropertyValue, indent: &str, unit: &str) -> String {
+    let mut buffer = vec![];
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    value.serialize(&mut serializer).unwrap();
+    String::from_utf8(buffer).unwrap().replace('\n', &format!("\n{}", indent))
+}
@@ -2,3 +2,12 @@ pub static ROOT: &str = "/";
 pub static PATH_SEPARATOR: &str = "/";
 pub static NAME: &str = "name";
 pub static CHILDREN: &str = "children";
+pub static GIT_DIR: &str = ".git";
+pub static GITIGNORE_FILE: &str = ".gitignore";
+pub static WISPHAIGNORE_FILE: &str = ".wisphaignore";
+pub static TYPE: &str = "type";
+pub static TARGET: &str = "target";
+pub static DIRECT: &str = "Direct";
+pub static LINK: &str = "Link";
+pub static KEY_PATH_SEPARATOR: &str = ".";
+pub static STRUCTURE_KEYS: [&str; 4] = ["name", "type", "target", "children"];
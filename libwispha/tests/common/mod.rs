@@ -0,0 +1,81 @@
+//! Setup shared by integration tests. Each test file uses only part of it
+#![allow(dead_code)]
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+
+use std::fs;
+use std::path::Path;
+
+use tempfile::TempDir;
+
+/// Root record file of the project made by `project`, where `a` is linked and `b` is recorded inline
+pub static ROOT_FILE: &str = r#"{
+  "name": "Project",
+  "description": "root",
+  "children": [
+    {
+      "name": "a",
+      "type": "Link",
+      "target": "a/LOOKME.json"
+    },
+    {
+      "name": "b"
+    }
+  ]
+}"#;
+
+/// Record file of `a`, which links `c`
+pub static A_FILE: &str = r#"{
+  "children": [
+    {
+      "name": "x",
+      "description": "x"
+    },
+    {
+      "name": "c",
+      "type": "Link",
+      "target": "c/LOOKME.json"
+    }
+  ]
+}"#;
+
+/// An empty tree of project "Project"
+pub fn tree() -> Tree {
+    Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    })
+}
+
+/// A tree of project "Project", whose nodes are read from `content` recorded in `record_file`
+pub fn tree_from_str(content: &str, record_file: impl AsRef<Path>) -> Tree {
+    let tree = tree();
+    tree.insert_nodes_from_str(content, record_file.as_ref().to_path_buf(), None, &vec![]).unwrap();
+    tree
+}
+
+/// The property at `key` of the node at `path`, if the node is a direct node
+pub fn property(tree: &Tree, path: &str, key: &str) -> Option<PropertyValue> {
+    let node = tree.get_node(&NodePath::from(path, tree).unwrap())?;
+    let node = node.borrow();
+    node.get_direct()?.get_property(key).cloned()
+}
+
+/// A project directory with `ROOT_FILE`, `a/LOOKME.json` and `a/c/LOOKME.json`
+pub fn project() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("a/c")).unwrap();
+    fs::write(root.join("LOOKME.json"), ROOT_FILE).unwrap();
+    fs::write(root.join("a/LOOKME.json"), A_FILE).unwrap();
+    fs::write(root.join("a/c/LOOKME.json"), r#"{"description": "c"}"#).unwrap();
+    dir
+}
+
+/// Load the project at `root`, resolving links within `depth`
+pub fn load(root: &Path, depth: usize) -> Tree {
+    let root_file = root.join("LOOKME.json");
+    let tree = tree_from_str(&fs::read_to_string(&root_file).unwrap(), root_file);
+    tree.resolve_in_depth(&NodePath::new(&tree), depth, &FsResolver, &vec![]).unwrap();
+    tree
+}
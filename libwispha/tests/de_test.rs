@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 use libwispha::core::*;
 use libwispha::serde::de::Error;
 
@@ -45,10 +47,104 @@ fn lack_target() {
                                          &vec![]);
     if let Err(error) = res {
         match error {
-            Error::LackTarget => assert!(true),
+            Error::LackTarget(_) => assert!(true),
             _ => assert!(false)
         }
     } else {
         assert!(false)
     }
 }
+
+#[test]
+fn typed_properties() {
+    let json_str = r#"{"since": 2019, "deprecated": true, "tags": ["io", "net"], "owner": {"team": "infra", "lead": null}, "a.b": "dotted"}"#;
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    tree.insert_nodes_from_str(json_str,
+                               PathBuf::from("LOOKME.json"),
+                               None,
+                               &vec![]).unwrap();
+    let root = tree.root().unwrap();
+    let root = root.borrow();
+    let direct_node = root.get_direct().unwrap();
+    assert_eq!(direct_node.get_property("since"), Some(&PropertyValue::Integer(2019)));
+    assert_eq!(direct_node.get_property("deprecated"), Some(&PropertyValue::Bool(true)));
+    assert_eq!(direct_node.get_property("owner.team"), Some(&PropertyValue::String("infra".to_string())));
+    assert_eq!(direct_node.get_property("tags.1"), Some(&PropertyValue::String("net".to_string())));
+    assert_eq!(direct_node.get_property("a.b"), Some(&PropertyValue::String("dotted".to_string())));
+    assert_eq!(direct_node.get_property("owner.name"), None);
+    assert_eq!(direct_node.get_property("tags").unwrap().to_string(), "[io, net]");
+    assert_eq!(direct_node.get_property("owner.lead"), Some(&PropertyValue::Null));
+    assert_eq!(direct_node.get_property("owner").unwrap().to_string(), "{team: infra, lead: null}");
+    assert!(tree.to_string().unwrap().contains("\"lead\":null"));
+}
+
+#[test]
+fn name_not_string() {
+    let json_str = r#"{"children": [{"name": 1}]}"#;
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    let res = tree.insert_nodes_from_str(json_str,
+                                         PathBuf::from("LOOKME.json"),
+                                         None,
+                                         &vec![]);
+    assert!(matches!(res, Err(Error::NotString(_))));
+}
+
+#[test]
+fn located_errors() {
+    let json_str = "{\n  \"children\": [\n    {\"name\": \"a\"},\n    {\"description\": \"no name\"}\n  ]\n}";
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    let error = tree.insert_nodes_from_str(json_str,
+                                           PathBuf::from("LOOKME.json"),
+                                           None,
+                                           &vec![]).unwrap_err();
+    assert!(matches!(error, Error::LackName(_)));
+    let location = error.location();
+    assert_eq!(location.record_file, PathBuf::from("LOOKME.json"));
+    assert_eq!((location.line, location.column), (4, 5));
+    assert_eq!(location.node_path.to_string(), "/");
+    assert_eq!(location.snippet(), " --> LOOKME.json:4:5\n  |\n4 |     {\"description\": \"no name\"}\n  |     ^");
+
+    let json_str = "{\n  \"children\": [{\"name\": \"a\", \"children\": [{\"name\": \"b\", \"path\": 1}]}]\n}";
+    let error = tree.insert_nodes_from_str(json_str,
+                                           PathBuf::from("LOOKME.json"),
+                                           None,
+                                           &vec!["path"]).unwrap_err();
+    assert!(matches!(error, Error::PreservedKey(_)));
+    assert_eq!((error.location().line, error.location().column), (2, 57));
+    assert_eq!(error.location().node_path.to_string(), "/a/b");
+
+    let json_str = "{\n  \"description\": 1\n  \"a\": 2\n}";
+    let error = tree.insert_nodes_from_str(json_str,
+                                           PathBuf::from("LOOKME.json"),
+                                           None,
+                                           &vec![]).unwrap_err();
+    assert!(matches!(error, Error::ParsingFailed(_)));
+    assert_eq!(error.location().line, 3);
+}
+
+#[test]
+fn node_spans() {
+    let json_str = "{\n  \"children\": [\n    {\"name\": \"a\"},\n    {\"name\": \"b\", \"type\": \"Link\", \"target\": \"b.json\"}\n  ]\n}";
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    tree.insert_nodes_from_str(json_str,
+                               PathBuf::from("LOOKME.json"),
+                               None,
+                               &vec![]).unwrap();
+    let span = |path: &str| tree.get_node(&NodePath::from(path, &tree).unwrap()).unwrap().borrow().node_properties().span.unwrap();
+    let root = span("/");
+    assert_eq!((root.start, root.end), (0, json_str.len()));
+    assert_eq!((root.end_line, root.end_column), (6, 1));
+    let a = span("/a");
+    assert_eq!(&json_str[a.start..a.end], "{\"name\": \"a\"}");
+    assert_eq!((a.start_line, a.start_column, a.end_line, a.end_column), (3, 5, 3, 17));
+    let b = span("/b");
+    assert_eq!((b.start_line, b.start_column), (4, 5));
+}
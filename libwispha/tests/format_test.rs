@@ -0,0 +1,187 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::resolver::{FsResolver, MemoryResolver};
+use libwispha::serde::de;
+use libwispha::serde::format::{self, Json, Toml, Yaml};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+static ROOT_JSON: &str = r#"{
+  "description": "root",
+  "children": [
+    {"name": "a", "type": "Link", "target": "a/LOOKME.toml"},
+    {"name": "b", "type": "Link", "target": "b/LOOKME.yaml"}
+  ]
+}"#;
+
+static A_TOML: &str = r#"description = "a"
+
+[owner]
+team = "core"
+
+[[children]]
+name = "x"
+tags = ["one", "two"]
+
+[[children]]
+name = "c"
+type = "Link"
+target = "c/LOOKME.json"
+"#;
+
+static B_YAML: &str = "description: b
+children:
+  - name: y
+    size: 3
+";
+
+static C_JSON: &str = r#"{"description": "c"}"#;
+
+fn resolver() -> MemoryResolver {
+    vec![
+        ("/a/LOOKME.toml", A_TOML),
+        ("/b/LOOKME.yaml", B_YAML),
+        ("/a/c/LOOKME.json", C_JSON),
+    ].into_iter().collect()
+}
+
+#[test]
+fn format_by_extension() {
+    assert_eq!(format::format_of(Path::new("LOOKME.json")).name(), "json");
+    assert_eq!(format::format_of(Path::new("a/LOOKME.toml")).name(), "toml");
+    assert_eq!(format::format_of(Path::new("LOOKME.yml")).name(), "yaml");
+    assert_eq!(format::format_of(Path::new("LOOKME")).name(), "json");
+    assert_eq!(format::format_named("yaml").unwrap().extensions(), &["yaml", "yml"]);
+    assert!(format::format_named("xml").is_none());
+}
+
+#[test]
+fn link_across_formats() {
+    let tree = tree_from_str(ROOT_JSON, "/LOOKME.json");
+    tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &resolver(), &vec![]).unwrap();
+
+    assert_eq!(property(&tree, "/a", "owner.team"), Some(PropertyValue::String("core".to_string())));
+    assert_eq!(property(&tree, "/a/x", "tags"), Some(PropertyValue::Array(vec![
+        PropertyValue::String("one".to_string()),
+        PropertyValue::String("two".to_string()),
+    ])));
+    assert_eq!(property(&tree, "/a/c", "description"), Some(PropertyValue::String("c".to_string())));
+    assert_eq!(property(&tree, "/b/y", "size"), Some(PropertyValue::Integer(3)));
+
+    let node = tree.get_node(&NodePath::from("/b/y", &tree).unwrap()).unwrap();
+    assert_eq!(node.borrow().node_properties().record_file, PathBuf::from("/b/LOOKME.yaml"));
+}
+
+#[test]
+fn same_structure_in_every_format() {
+    let json = r#"{"description": "d", "children": [{"name": "l", "type": "Link", "target": "l/LOOKME.json"}]}"#;
+    let toml = "description = \"d\"\n[[children]]\nname = \"l\"\ntype = \"Link\"\ntarget = \"l/LOOKME.json\"\n";
+    let yaml = "description: d\nchildren:\n  - name: l\n    type: Link\n    target: l/LOOKME.json\n";
+    let mut strings = vec![];
+    for (content, file) in [(json, "/LOOKME.json"), (toml, "/LOOKME.toml"), (yaml, "/LOOKME.yaml")] {
+        let tree = tree_from_str(content, file);
+        let node = tree.get_node(&NodePath::from("/l", &tree).unwrap()).unwrap();
+        assert!(matches!(&*node.borrow(), Node::Link(link_node) if link_node.target == Path::new("l/LOOKME.json")));
+        strings.push(tree.to_string().unwrap());
+    }
+    assert_eq!(strings[0], strings[1]);
+    assert_eq!(strings[0], strings[2]);
+}
+
+#[test]
+fn write_in_format() {
+    let tree = tree_from_str(A_TOML, "/LOOKME.toml");
+    for format in format::FORMATS.iter() {
+        let content = tree.to_string_in_format(ChildrenOrder::AsAuthored, *format).unwrap();
+        let reloaded = self::tree();
+        let file = PathBuf::from(format!("/LOOKME.{}", format.extensions()[0]));
+        reloaded.insert_nodes_from_str(&content, file, None, &vec![]).unwrap();
+        assert_eq!(reloaded.to_string().unwrap(), tree.to_string().unwrap());
+    }
+    let json = tree.to_string_in_format(ChildrenOrder::AsAuthored, &Json).unwrap();
+    assert!(json.starts_with("{\n  \"name\": \"Project\""));
+    let yaml = tree.to_string_in_format(ChildrenOrder::AsAuthored, &Yaml).unwrap();
+    assert!(yaml.starts_with("name: Project\ndescription: a\n"));
+    let toml = tree.to_string_in_format(ChildrenOrder::AsAuthored, &Toml).unwrap();
+    assert!(toml.contains("[[children]]\nname = \"x\""));
+}
+
+#[test]
+fn located_errors() {
+    let tree = tree();
+    let position = |error: de::Error| (error.location().line, error.location().column);
+
+    let res = tree.insert_nodes_from_str("description = \"a\"\n[[children]]\ntype = 1\n", PathBuf::from("/LOOKME.toml"), None, &vec![]);
+    assert!(matches!(res, Err(de::Error::NotString(_))));
+    assert_eq!(position(res.unwrap_err()), (3, 1));
+
+    let res = tree.insert_nodes_from_str("description = \"a\"\n\n[[children]]\nsize = 1\n", PathBuf::from("/LOOKME.toml"), None, &vec![]);
+    assert!(matches!(res, Err(de::Error::LackName(_))));
+    assert_eq!(position(res.unwrap_err()), (3, 1));
+
+    let res = tree.insert_nodes_from_str("description = \"a\"\nchildren = [{name = \"x\"}, {size = 1}]\n", PathBuf::from("/LOOKME.toml"), None, &vec![]);
+    assert_eq!(position(res.unwrap_err()), (2, 27));
+
+    let res = tree.insert_nodes_from_str("description: a\nchildren: x\n", PathBuf::from("/LOOKME.yaml"), None, &vec![]);
+    assert!(matches!(res, Err(de::Error::NotArray(_))));
+    assert_eq!(position(res.unwrap_err()), (2, 1));
+
+    let res = tree.insert_nodes_from_str("description: a\nchildren:\n  - name: x\n  - size: 1\n", PathBuf::from("/LOOKME.yaml"), None, &vec![]);
+    assert!(matches!(res, Err(de::Error::LackName(_))));
+    assert_eq!(position(res.unwrap_err()), (4, 5));
+
+    let res = tree.insert_nodes_from_str("description = \"a\"\nowner = \n", PathBuf::from("/LOOKME.toml"), None, &vec![]);
+    if let Err(de::Error::ParsingFailed((_, location))) = res {
+        assert_eq!(location.line, 2);
+    } else {
+        panic!("syntax error is not reported")
+    }
+
+    let res = tree.insert_nodes_from_str("description: a\nowner: team: core\n", PathBuf::from("/LOOKME.yml"), None, &vec![]);
+    if let Err(de::Error::ParsingFailed((_, location))) = res {
+        assert_eq!(location.line, 2);
+    } else {
+        panic!("syntax error is not reported")
+    }
+}
+
+#[test]
+fn save_in_format_of_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("LOOKME.json"), r#"{"children": [{"name": "a", "type": "Link", "target": "a/LOOKME.toml"}]}"#).unwrap();
+    fs::create_dir(root.join("a")).unwrap();
+    fs::write(root.join("a/LOOKME.toml"), A_TOML).unwrap();
+
+    let tree = tree();
+    let root_file = root.join("LOOKME.json");
+    tree.insert_nodes_from_str(&fs::read_to_string(&root_file).unwrap(), root_file, None, &vec![]).unwrap();
+    let a = NodePath::from("/a", &tree).unwrap();
+    tree.resolve_node(&a, &FsResolver, &vec![]).unwrap();
+    tree.set_property(&a, "description", PropertyValue::String("changed".to_string())).unwrap();
+
+    let written = tree.save().unwrap();
+    assert_eq!(written, vec![root.join("a/LOOKME.toml")]);
+    let saved = fs::read_to_string(root.join("a/LOOKME.toml")).unwrap();
+    assert!(saved.starts_with("description = \"changed\"\n"));
+    assert!(saved.contains("[[children]]\nname = \"c\"\ntype = \"Link\"\ntarget = \"c/LOOKME.json\""));
+}
+
+#[test]
+fn node_spans() {
+    let tree = tree_from_str(ROOT_JSON, "/LOOKME.json");
+    tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &resolver(), &vec![]).unwrap();
+    let position = |path: &str| {
+        let node = tree.get_node(&NodePath::from(path, &tree).unwrap()).unwrap();
+        let span = node.borrow().node_properties().span.unwrap();
+        (span.start_line, span.start_column, span.end_line, span.end_column)
+    };
+    assert_eq!(position("/a"), (1, 1, 13, 24));
+    assert_eq!(position("/a/x"), (6, 1, 8, 21));
+    assert_eq!(position("/b"), (1, 1, 4, 11));
+    assert_eq!(position("/b/y"), (3, 5, 4, 11));
+}
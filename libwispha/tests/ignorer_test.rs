@@ -0,0 +1,80 @@
+use libwispha::ignorer::Ignorer;
+
+use std::fs;
+
+use tempfile::TempDir;
+
+fn project() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::create_dir_all(root.join("src/build")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\nbuild\n").unwrap();
+    fs::write(root.join("src/.gitignore"), "!build/\n").unwrap();
+    fs::write(root.join(".wisphaignore"), "*.swp\n").unwrap();
+    dir
+}
+
+#[test]
+fn gitignore_rules() {
+    let dir = project();
+    let root = dir.path();
+    let ignorer = Ignorer::new(root, &[]).unwrap();
+    assert!(ignorer.is_ignored(&root.join("target"), true).unwrap());
+    // directory-only pattern
+    assert!(!ignorer.is_ignored(&root.join("target"), false).unwrap());
+    assert!(ignorer.is_ignored(&root.join("debug.log"), false).unwrap());
+    // negation
+    assert!(!ignorer.is_ignored(&root.join("keep.log"), false).unwrap());
+    assert!(!ignorer.is_ignored(&root.join("main.rs"), false).unwrap());
+}
+
+#[test]
+fn nested_ignore_file() {
+    let dir = project();
+    let root = dir.path();
+    let ignorer = Ignorer::new(root, &[]).unwrap();
+    assert!(ignorer.is_ignored(&root.join("build"), true).unwrap());
+    assert!(!ignorer.is_ignored(&root.join("src/build"), true).unwrap());
+}
+
+#[test]
+fn wisphaignore_and_excludes() {
+    let dir = project();
+    let root = dir.path();
+    let ignorer = Ignorer::new(root, &["docs/".to_string(), "!debug.log".to_string()]).unwrap();
+    assert!(ignorer.is_ignored(&root.join("src/main.rs.swp"), false).unwrap());
+    assert!(ignorer.is_ignored(&root.join("docs"), true).unwrap());
+    assert!(!ignorer.is_ignored(&root.join("debug.log"), false).unwrap());
+    assert!(ignorer.is_ignored(&root.join(".git"), true).unwrap());
+}
+
+#[test]
+fn nested_in_ignored_directory() {
+    let dir = project();
+    let root = dir.path();
+    let ignorer = Ignorer::new(root, &["docs/".to_string()]).unwrap();
+    assert!(ignorer.is_ignored(&root.join("target/debug/foo"), false).unwrap());
+    assert!(ignorer.is_ignored(&root.join("target/debug"), true).unwrap());
+    assert!(ignorer.is_ignored(&root.join("build/out.o"), false).unwrap());
+    assert!(!ignorer.is_ignored(&root.join("src/build/out.o"), false).unwrap());
+    assert!(ignorer.is_ignored(&root.join("docs/index.md"), false).unwrap());
+    assert!(ignorer.is_ignored(&root.join(".git/HEAD"), false).unwrap());
+    // a file named like a directory-only pattern is not ignored by itself
+    assert!(!ignorer.is_ignored(&root.join("src/target"), false).unwrap());
+}
+
+#[test]
+fn no_reinclusion_in_ignored_directory() {
+    let dir = project();
+    let root = dir.path();
+    fs::create_dir_all(root.join("target/keep")).unwrap();
+    fs::write(root.join("target/.gitignore"), "!keep\n").unwrap();
+    fs::write(root.join("src/.wisphaignore"), "!*.swp\n").unwrap();
+    let ignorer = Ignorer::new(root, &[]).unwrap();
+    assert!(ignorer.is_ignored(&root.join("target/keep"), true).unwrap());
+    assert!(ignorer.is_ignored(&root.join("target/keep/a.txt"), false).unwrap());
+    // re-including a file whose directory is not ignored still works
+    assert!(!ignorer.is_ignored(&root.join("src/main.rs.swp"), false).unwrap());
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::serde::de::Error;
+
+use std::fs;
+use std::path::PathBuf;
+
+static ROOT_FILE: &str = r#"// Layout of the project
+{
+    "description": "root", // TODO: describe after refactor
+    /* who to ask */
+    owner: 'core',
+    "children": [
+        {
+            "name": "a",
+            "description": "a",
+        },
+        // generated code
+        {
+            "name": "b",
+            "description": "b" // keep it short
+        },
+    ],
+}
+"#;
+
+#[test]
+fn parse_comments_and_trailing_commas() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    assert_eq!(property(&tree, "/b", "description"), Some(PropertyValue::String("b".to_string())));
+
+    let root = tree.root().unwrap();
+    assert_eq!(root.borrow().get_direct().unwrap().get_property("owner"), Some(&PropertyValue::String("core".to_string())));
+
+    let node = tree.get_node(&NodePath::from("/b", &tree).unwrap()).unwrap();
+    let span = node.borrow().node_properties().span.unwrap();
+    assert_eq!((span.start_line, span.start_column), (12, 9));
+    assert_eq!((span.end_line, span.end_column), (15, 9));
+}
+
+#[test]
+fn syntax_error_after_comment() {
+    let tree = tree();
+    let json_str = "{\n  // comment\n  \"description\": \"a\"\n  \"b\": 1\n}";
+    let error = tree.insert_nodes_from_str(json_str, PathBuf::from("LOOKME.jsonc"), None, &vec![]).unwrap_err();
+    assert!(matches!(error, Error::ParsingFailed(_)));
+    assert_eq!(error.location().line, 4);
+}
+
+#[test]
+fn comments_kept_when_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let record_file = dir.path().join("LOOKME.json");
+    fs::write(&record_file, ROOT_FILE).unwrap();
+
+    let tree = tree();
+    tree.insert_nodes_from_str(ROOT_FILE, record_file.clone(), None, &vec![]).unwrap();
+    let root = NodePath::new(&tree);
+    let a = NodePath::from("/a", &tree).unwrap();
+    let b = NodePath::from("/b", &tree).unwrap();
+    tree.set_property(&root, "description", PropertyValue::String("the root".to_string())).unwrap();
+    tree.set_property(&b, "size", PropertyValue::Integer(3)).unwrap();
+    tree.remove_subtree(&a).unwrap();
+    tree.add_child(&root, "c").unwrap();
+    tree.save().unwrap();
+
+    let saved = fs::read_to_string(&record_file).unwrap();
+    assert_eq!(saved, r#"// Layout of the project
+{
+    "description": "the root", // TODO: describe after refactor
+    /* who to ask */
+    owner: 'core',
+    "children": [
+        // generated code
+        {
+            "name": "b",
+            "description": "b", // keep it short
+            "size": 3
+        },
+        {
+            "name": "c"
+        },
+    ],
+}
+"#);
+
+    // Spans are located in the rewritten file
+    let node = tree.get_node(&NodePath::from("/c", &tree).unwrap()).unwrap();
+    let span = node.borrow().node_properties().span.unwrap();
+    assert_eq!((span.start_line, span.start_column), (13, 9));
+
+    // Nothing is written if nothing changes
+    assert!(tree.save().unwrap().is_empty());
+}
+
+#[test]
+fn inline_objects_are_edited_inline() {
+    let dir = tempfile::tempdir().unwrap();
+    let record_file = dir.path().join("LOOKME.json5");
+    let content = "{description: 'root', children: [{name: 'a'}] /* only one */}\n";
+    fs::write(&record_file, content).unwrap();
+
+    let tree = tree();
+    tree.insert_nodes_from_str(content, record_file.clone(), None, &vec![]).unwrap();
+    let a = NodePath::from("/a", &tree).unwrap();
+    tree.set_property(&a, "description", PropertyValue::String("a".to_string())).unwrap();
+    tree.save().unwrap();
+
+    let saved = fs::read_to_string(&record_file).unwrap();
+    assert_eq!(saved, "{description: 'root', children: [{name: 'a', \"description\": \"a\"}] /* only one */}\n");
+}
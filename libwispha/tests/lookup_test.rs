@@ -0,0 +1,50 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use libwispha::manipulator::Error;
+
+fn is_link(tree: &Tree, path: &str) -> bool {
+    let node = tree.get_node(&NodePath::from(path, tree).unwrap()).unwrap();
+    let is_link = matches!(&*node.borrow(), Node::Link(_));
+    is_link
+}
+
+#[test]
+fn path_through_link_test() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 0);
+    let node_path = tree.node_path_of(&root.join("a/x"), &FsResolver, &vec![]).unwrap();
+    assert_eq!(node_path.to_string(), "/a/x");
+    let node = tree.get_node(&node_path).unwrap();
+    assert_eq!(node.borrow().get_direct().unwrap().get_property("description"),
+               Some(&PropertyValue::String("x".to_string())));
+    // Links not on the way are left unresolved
+    assert!(!is_link(&tree, "/a"));
+    assert!(is_link(&tree, "/a/c"));
+}
+
+#[test]
+fn unnormalized_path_test() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 0);
+    let node_path = tree.node_path_of(&root.join("b/../a/./x"), &FsResolver, &vec![]).unwrap();
+    assert_eq!(node_path.to_string(), "/a/x");
+    let node_path = tree.node_path_of(root, &FsResolver, &vec![]).unwrap();
+    assert_eq!(node_path.to_string(), "/");
+}
+
+#[test]
+fn path_not_in_tree_test() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 0);
+    assert!(matches!(tree.node_path_of(&root.join("a/y"), &FsResolver, &vec![]),
+                     Err(Error::PathNotInTree(_))));
+    assert!(matches!(tree.node_path_of(root.parent().unwrap(), &FsResolver, &vec![]),
+                     Err(Error::PathNotInTree(_))));
+}
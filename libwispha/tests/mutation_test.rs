@@ -0,0 +1,134 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::manipulator::Error;
+
+use std::path::PathBuf;
+
+static ROOT_FILE: &str = r#"{"children": [
+    {"name": "a", "children": [{"name": "a1", "children": [{"name": "deep"}]}, {"name": "a2"}]},
+    {"name": "b", "description": "b"},
+    {"name": "c", "type": "Link", "target": "c/LOOKME.json"}
+]}"#;
+
+fn path(raw_path: &str, tree: &Tree) -> NodePath {
+    NodePath::from(raw_path, tree).unwrap()
+}
+
+fn children_of(raw_path: &str, tree: &Tree) -> Vec<String> {
+    let node = tree.get_node(&path(raw_path, tree)).unwrap();
+    let node = node.borrow();
+    node.get_direct().unwrap().children.iter().map(|child| child.to_string()).collect()
+}
+
+#[test]
+fn remove_subtree() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    tree.remove_subtree(&path("/a", &tree)).unwrap();
+    assert!(tree.get_node(&path("/a", &tree)).is_none());
+    assert!(tree.get_node(&path("/a/a1/deep", &tree)).is_none());
+    assert_eq!(children_of("/", &tree), vec!["/b", "/c"]);
+    assert!(matches!(tree.remove_subtree(&path("/a", &tree)), Err(Error::PathNotFound(_))));
+    assert!(matches!(tree.remove_subtree(&path("/", &tree)), Err(Error::RootNotAllowed)));
+    assert!(tree.root().is_some());
+    assert_eq!(children_of("/", &tree), vec!["/b", "/c"]);
+}
+
+#[test]
+fn rename_node() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    let new_path = tree.rename_node(&path("/a", &tree), "x").unwrap();
+    assert_eq!(new_path.to_string(), "/x");
+    assert_eq!(children_of("/", &tree), vec!["/x", "/b", "/c"]);
+    assert_eq!(children_of("/x", &tree), vec!["/x/a1", "/x/a2"]);
+    assert_eq!(children_of("/x/a1", &tree), vec!["/x/a1/deep"]);
+    let deep = tree.get_node(&path("/x/a1/deep", &tree)).unwrap();
+    assert_eq!(deep.borrow().node_properties().parent.unwrap().to_string(), "/x/a1");
+    assert_eq!(tree.get_node(&path("/x", &tree)).unwrap().borrow().node_properties().name, "x");
+    assert!(tree.get_node(&path("/a", &tree)).is_none());
+
+    assert!(matches!(tree.rename_node(&path("/x", &tree), "b"), Err(Error::NodeExists(_))));
+    assert!(matches!(tree.rename_node(&path("/x", &tree), "y/z"), Err(Error::InvalidName(_))));
+}
+
+#[test]
+fn move_node() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    let new_path = tree.move_node(&path("/a/a1", &tree), &path("/b", &tree)).unwrap();
+    assert_eq!(new_path.to_string(), "/b/a1");
+    assert_eq!(children_of("/a", &tree), vec!["/a/a2"]);
+    assert_eq!(children_of("/b", &tree), vec!["/b/a1"]);
+    let deep = tree.get_node(&path("/b/a1/deep", &tree)).unwrap();
+    assert_eq!(deep.borrow().node_properties().parent.unwrap().to_string(), "/b/a1");
+
+    assert!(matches!(tree.move_node(&path("/b", &tree), &path("/b/a1", &tree)),
+                     Err(Error::MoveIntoDescendant(_))));
+    assert!(matches!(tree.move_node(&path("/a/a2", &tree), &path("/c", &tree)),
+                     Err(Error::UnresolvedLink(_))));
+    tree.rename_node(&path("/a/a2", &tree), "a1").unwrap();
+    assert!(matches!(tree.move_node(&path("/a/a1", &tree), &path("/b", &tree)),
+                     Err(Error::NodeExists(_))));
+}
+
+#[test]
+fn set_and_remove_property() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    let b = path("/b", &tree);
+    tree.set_property(&b, "owner", PropertyValue::String("infra".to_string())).unwrap();
+    let old = tree.set_property(&b, "description", PropertyValue::Bool(true)).unwrap();
+    assert_eq!(old, Some(PropertyValue::String("b".to_string())));
+    assert_eq!(tree.remove_property(&b, "description").unwrap(), Some(PropertyValue::Bool(true)));
+    {
+        let node = tree.get_node(&b).unwrap();
+        let node = node.borrow();
+        assert_eq!(node.get_direct().unwrap().properties.keys().collect::<Vec<_>>(), vec!["owner"]);
+    }
+
+    assert!(matches!(tree.set_property(&b, "name", PropertyValue::Bool(true)), Err(Error::KeyNotAllowed(_))));
+    assert!(matches!(tree.set_property(&path("/c", &tree), "key", PropertyValue::Bool(true)),
+                     Err(Error::UnresolvedLink(_))));
+}
+
+#[test]
+fn set_and_remove_nested_property() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    let b = path("/b", &tree);
+    let get = |key_path: &str| {
+        let node = tree.get_node(&b).unwrap();
+        let node = node.borrow();
+        node.get_direct().unwrap().get_property(key_path).cloned()
+    };
+    tree.set_property(&b, "owner.team", PropertyValue::String("infra".to_string())).unwrap();
+    assert_eq!(get("owner.team"), Some(PropertyValue::String("infra".to_string())));
+    assert_eq!(get("owner").unwrap().to_string(), "{team: infra}");
+
+    tree.set_property(&b, "tags", PropertyValue::Array(vec![PropertyValue::Integer(1)])).unwrap();
+    let old = tree.set_property(&b, "tags.0", PropertyValue::Integer(2)).unwrap();
+    assert_eq!(old, Some(PropertyValue::Integer(1)));
+    tree.set_property(&b, "tags.1", PropertyValue::Integer(3)).unwrap();
+    assert_eq!(get("tags").unwrap().to_string(), "[2, 3]");
+    assert!(matches!(tree.set_property(&b, "tags.5", PropertyValue::Integer(4)), Err(Error::InvalidKeyPath(_))));
+    assert!(matches!(tree.set_property(&b, "description.x", PropertyValue::Integer(4)), Err(Error::InvalidKeyPath(_))));
+    assert!(matches!(tree.set_property(&b, "children.x", PropertyValue::Integer(4)), Err(Error::KeyNotAllowed(_))));
+
+    assert_eq!(tree.remove_property(&b, "tags.0").unwrap(), Some(PropertyValue::Integer(2)));
+    assert_eq!(tree.remove_property(&b, "owner.team").unwrap(), Some(PropertyValue::String("infra".to_string())));
+    assert_eq!(tree.remove_property(&b, "owner.team").unwrap(), None);
+    assert_eq!(get("owner").unwrap().to_string(), "{}");
+    assert_eq!(get("tags").unwrap().to_string(), "[3]");
+}
+
+#[test]
+fn add_child() {
+    let tree = tree_from_str(ROOT_FILE, "/LOOKME.json");
+    let new_path = tree.add_child(&path("/b", &tree), "new").unwrap();
+    assert_eq!(new_path.to_string(), "/b/new");
+    assert_eq!(children_of("/b", &tree), vec!["/b/new"]);
+    let node = tree.get_node(&new_path).unwrap();
+    assert_eq!(node.borrow().node_properties().record_file, PathBuf::from("/LOOKME.json"));
+
+    assert!(matches!(tree.add_child(&path("/b", &tree), "new"), Err(Error::NodeExists(_))));
+    assert!(matches!(tree.add_child(&path("/c", &tree), "new"), Err(Error::UnresolvedLink(_))));
+}
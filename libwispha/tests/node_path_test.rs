@@ -0,0 +1,29 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::manipulator::Error;
+
+#[test]
+fn absolute_path() {
+    let tree = tree();
+    assert_eq!(NodePath::from("/", &tree).unwrap().to_string(), "/");
+    assert_eq!(NodePath::from("/a/b/", &tree).unwrap().to_string(), "/a/b");
+    assert_eq!(NodePath::from("/a/./b/../c", &tree).unwrap().to_string(), "/a/c");
+    assert!(matches!(NodePath::from("a/b", &tree), Err(Error::NodePathMustBeAbsolute(_))));
+}
+
+#[test]
+fn relative_path() {
+    let tree = tree();
+    let base = NodePath::from("/a/b", &tree).unwrap();
+    assert_eq!(base.join("c").to_string(), "/a/b/c");
+    assert_eq!(base.join("./c/d").to_string(), "/a/b/c/d");
+    assert_eq!(base.join("..").to_string(), "/a");
+    assert_eq!(base.join("../x").to_string(), "/a/x");
+    assert_eq!(base.join(".").to_string(), "/a/b");
+    assert_eq!(base.join("/x").to_string(), "/x");
+    // parent of root is root
+    assert_eq!(base.join("../../..").to_string(), "/");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::manipulator::Error;
+use libwispha::resolver::MemoryResolver;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use maplit::*;
+
+fn resolver(files: &HashMap<&'static str, &'static str>) -> MemoryResolver {
+    files.iter().map(|(path, content)| (*path, *content)).collect()
+}
+
+#[test]
+fn indirect_cycle() {
+    let files = hashmap! {
+        "/LOOKME.json" => r#"{"children": [{"name": "a", "type": "Link", "target": "a/LOOKME.json"}]}"#,
+        "/a/LOOKME.json" => r#"{"children": [{"name": "b", "type": "Link", "target": "../b/LOOKME.json"}]}"#,
+        "/b/LOOKME.json" => r#"{"children": [{"name": "a", "type": "Link", "target": "../a/LOOKME.json"}]}"#,
+    };
+    let tree = tree_from_str(files["/LOOKME.json"], "/LOOKME.json");
+    let res = tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &resolver(&files), &vec![]);
+    if let Err(Error::LinkCycle(cycle)) = res {
+        assert_eq!(cycle, vec![PathBuf::from("/a/LOOKME.json"),
+                               PathBuf::from("/b/LOOKME.json"),
+                               PathBuf::from("/a/LOOKME.json")]);
+    } else {
+        panic!("cycle is not detected")
+    }
+}
+
+#[test]
+fn link_to_ancestor() {
+    let files = hashmap! {
+        "/LOOKME.json" => r#"{"children": [{"name": "a", "type": "Link", "target": "a/LOOKME.json"}]}"#,
+        "/a/LOOKME.json" => r#"{"children": [{"name": "up", "type": "Link", "target": "../LOOKME.json"}]}"#,
+    };
+    let tree = tree_from_str(files["/LOOKME.json"], "/LOOKME.json");
+    let node_path = NodePath::from("/a/up/a", &tree).unwrap();
+    let res = tree.resolve_node(&node_path, &resolver(&files), &vec![]);
+    assert!(matches!(res, Err(Error::LinkCycle(_))));
+}
+
+#[test]
+fn chained_link_cycle() {
+    let files = hashmap! {
+        "/LOOKME.json" => r#"{"children": [{"name": "a", "type": "Link", "target": "a/LOOKME.json"}]}"#,
+        "/a/LOOKME.json" => r#"{"type": "Link", "target": "b/LOOKME.json"}"#,
+        "/a/b/LOOKME.json" => r#"{"type": "Link", "target": "../LOOKME.json"}"#,
+    };
+    let tree = tree_from_str(files["/LOOKME.json"], "/LOOKME.json");
+    let node_path = NodePath::from("/a", &tree).unwrap();
+    let res = tree.resolve_node(&node_path, &resolver(&files), &vec![]);
+    assert!(matches!(res, Err(Error::LinkCycle(_))));
+}
+
+#[test]
+fn shared_target_is_not_cycle() {
+    let files = hashmap! {
+        "/LOOKME.json" => r#"{"children": [
+            {"name": "a", "type": "Link", "target": "common/LOOKME.json"},
+            {"name": "b", "type": "Link", "target": "common/LOOKME.json"}
+        ]}"#,
+        "/common/LOOKME.json" => r#"{"description": "common"}"#,
+    };
+    let tree = tree_from_str(files["/LOOKME.json"], "/LOOKME.json");
+    let res = tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &resolver(&files), &vec![]);
+    assert!(res.is_ok());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::resolver::*;
+
+use std::cell::Cell;
+use std::error;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn memory_resolver_normalizes_paths() {
+    let resolver = vec![
+        ("/b/LOOKME.json", r#"{"description": "b"}"#),
+    ].into_iter().collect::<MemoryResolver>();
+    let tree = tree_from_str(r#"{"children": [{"name": "b", "type": "Link", "target": "a/../b/./LOOKME.json"}]}"#,
+                             "/LOOKME.json");
+    tree.resolve_node(&NodePath::from("/b", &tree).unwrap(), &resolver, &vec![]).unwrap();
+    assert_eq!(property(&tree, "/b", "description"), Some(PropertyValue::String("b".to_string())));
+    assert_eq!(resolver.get("/a/../b/LOOKME.json").map(|content| content.as_str()), Some(r#"{"description": "b"}"#));
+
+    let tree = tree_from_str(r#"{"children": [{"name": "c", "type": "Link", "target": "c/LOOKME.json"}]}"#,
+                             "/LOOKME.json");
+    assert!(tree.resolve_node(&NodePath::from("/c", &tree).unwrap(), &resolver, &vec![]).is_err());
+}
+
+#[test]
+fn fs_resolver_reads_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("a")).unwrap();
+    fs::write(root.join("a/LOOKME.json"), r#"{"description": "a"}"#).unwrap();
+    let root_file = root.join("LOOKME.json");
+    let tree = tree_from_str(r#"{"children": [
+        {"name": "a", "type": "Link", "target": "a/LOOKME.json"},
+        {"name": "self", "type": "Link", "target": "LOOKME.json"},
+        {"name": "missing", "type": "Link", "target": "missing/LOOKME.json"}
+    ]}"#, root_file.to_str().unwrap());
+    tree.resolve_node(&NodePath::from("/a", &tree).unwrap(), &FsResolver, &vec![]).unwrap();
+    assert_eq!(property(&tree, "/a", "description"), Some(PropertyValue::String("a".to_string())));
+    assert!(tree.resolve_node(&NodePath::from("/self", &tree).unwrap(), &FsResolver, &vec![]).is_err());
+    assert!(tree.resolve_node(&NodePath::from("/missing", &tree).unwrap(), &FsResolver, &vec![]).is_err());
+}
+
+#[test]
+fn caching_resolver_loads_once() {
+    let loads = Cell::new(0);
+    let counting = |link_node: &LinkNode| -> Result<(PathBuf, String), Box<dyn error::Error>> {
+        loads.set(loads.get() + 1);
+        Ok((target_path(link_node), r#"{"description": "common"}"#.to_string()))
+    };
+    let resolver = CachingResolver::new(counting);
+    let json_str = r#"{"children": [
+        {"name": "a", "type": "Link", "target": "common/LOOKME.json"},
+        {"name": "b", "type": "Link", "target": "./common/LOOKME.json"}
+    ]}"#;
+    let tree = tree_from_str(json_str, "/LOOKME.json");
+    tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &resolver, &vec![]).unwrap();
+    assert_eq!(loads.get(), 1);
+    assert_eq!(property(&tree, "/b", "description"), Some(PropertyValue::String("common".to_string())));
+
+    resolver.clear();
+    let tree = tree_from_str(json_str, "/LOOKME.json");
+    tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &resolver, &vec![]).unwrap();
+    assert_eq!(loads.get(), 2);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::*;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+
+use std::fs;
+
+#[test]
+fn unchanged_tree_writes_nothing() {
+    let dir = project();
+    let tree = load(dir.path(), 1);
+    assert!(tree.save().unwrap().is_empty());
+}
+
+#[test]
+fn only_changed_file_is_written() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 1);
+    tree.set_property(&NodePath::from("/a/x", &tree).unwrap(),
+                      "owner",
+                      PropertyValue::String("infra".to_string())).unwrap();
+    assert_eq!(tree.save().unwrap(), vec![root.join("a/LOOKME.json")]);
+    assert_eq!(fs::read_to_string(root.join("LOOKME.json")).unwrap(), ROOT_FILE);
+
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("a/LOOKME.json")).unwrap()).unwrap();
+    assert_eq!(saved, serde_json::json!({
+        "children": [
+            {"name": "x", "description": "x", "owner": "infra"},
+            {"name": "c", "type": "Link", "target": "c/LOOKME.json"}
+        ]
+    }));
+}
+
+#[test]
+fn moved_node_is_written_to_new_file() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 1);
+    tree.move_node(&NodePath::from("/a/x", &tree).unwrap(), &NodePath::new(&tree)).unwrap();
+    tree.move_node(&NodePath::from("/a/c", &tree).unwrap(), &NodePath::from("/b", &tree).unwrap()).unwrap();
+    let mut written = tree.save().unwrap();
+    written.sort();
+    assert_eq!(written, vec![root.join("LOOKME.json"), root.join("a/LOOKME.json")]);
+
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("LOOKME.json")).unwrap()).unwrap();
+    assert_eq!(saved, serde_json::json!({
+        "name": "Project",
+        "description": "root",
+        "children": [
+            {"name": "a", "type": "Link", "target": "a/LOOKME.json"},
+            {"name": "b", "children": [{"name": "c", "type": "Link", "target": "a/c/LOOKME.json"}]},
+            {"name": "x", "description": "x"}
+        ]
+    }));
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("a/LOOKME.json")).unwrap()).unwrap();
+    assert_eq!(saved, serde_json::json!({}));
+}
+
+#[test]
+fn spans_are_updated() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 1);
+    let span = |path: &str| tree.get_node(&NodePath::from(path, &tree).unwrap()).unwrap().borrow().node_properties().span;
+    tree.add_child(&NodePath::from("/a", &tree).unwrap(), "y").unwrap();
+    assert_eq!(span("/a/y"), None);
+    tree.move_node(&NodePath::from("/b", &tree).unwrap(), &NodePath::from("/a", &tree).unwrap()).unwrap();
+    assert_eq!(span("/a/b"), None);
+    tree.save().unwrap();
+
+    let content = fs::read_to_string(root.join("a/LOOKME.json")).unwrap();
+    let y = span("/a/y").unwrap();
+    assert_eq!(&content[y.start..y.end], "{\n      \"name\": \"y\"\n    }");
+    let b = span("/a/b").unwrap();
+    assert_eq!(&content[b.start..b.end], "{\n      \"name\": \"b\"\n    }");
+    assert!(b.start_line > y.start_line);
+}
+
+#[test]
+fn nodes_are_written_to_their_new_record_files() {
+    let dir = project();
+    let root = dir.path();
+    let tree = load(root, 1);
+    tree.resolve_in_depth(&NodePath::new(&tree), usize::MAX, &FsResolver, &vec![]).unwrap();
+    for path in &["/a", "/a/x", "/a/c"] {
+        tree.set_record_file(&NodePath::from(path, &tree).unwrap(), root.join("LOOKME.json")).unwrap();
+    }
+    tree.set_record_file(&NodePath::from("/b", &tree).unwrap(), root.join("b/LOOKME.json")).unwrap();
+    fs::create_dir(root.join("b")).unwrap();
+    let mut written = tree.save().unwrap();
+    written.sort();
+    assert_eq!(written, vec![root.join("LOOKME.json"), root.join("b/LOOKME.json")]);
+
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("LOOKME.json")).unwrap()).unwrap();
+    assert_eq!(saved, serde_json::json!({
+        "name": "Project",
+        "description": "root",
+        "children": [
+            {"name": "a", "children": [
+                {"name": "x", "description": "x"},
+                {"name": "c", "description": "c"}
+            ]},
+            {"name": "b", "type": "Link", "target": "b/LOOKME.json"}
+        ]
+    }));
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("b/LOOKME.json")).unwrap()).unwrap();
+    assert_eq!(saved, serde_json::json!({"name": "b"}));
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 use libwispha::core::*;
 use libwispha::serde::ser::Error;
 
@@ -5,7 +7,7 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use std::path::PathBuf;
 
-use maplit::*;
+use indexmap::indexmap;
 
 #[test]
 fn to_json_test() {
@@ -23,9 +25,11 @@ fn to_json_test() {
         node_properties: NodeProperties {
             name: "TestProject".to_string(),
             parent: None,
-            record_file: PathBuf::from("LOOKME.json")
+            record_file: PathBuf::from("LOOKME.json"),
+            span: None
         },
-        properties: hashmap!{"description".to_string() => "Project directory".to_string()}
+        properties: indexmap!{"description".to_string() => PropertyValue::String("Project directory".to_string())},
+        link_target: None
     })));
 
     tree.insert_node(root_path.clone(), root);
@@ -35,9 +39,11 @@ fn to_json_test() {
         node_properties: NodeProperties {
             name: "subnode1".to_string(),
             parent: Some(root_path.clone()),
-            record_file: PathBuf::from("LOOKME.json")
+            record_file: PathBuf::from("LOOKME.json"),
+            span: None
         },
-        properties: hashmap!{"description".to_string() => "subnode1".to_string()}
+        properties: indexmap!{"description".to_string() => PropertyValue::String("subnode1".to_string())},
+        link_target: None
     })));
 
     tree.insert_node(subnode1_path.clone(), subnode1);
@@ -61,4 +67,52 @@ fn none_tree_test() {
     } else {
         assert!(false)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn preserve_order_test() {
+    let json_str = r#"{"name":"Project","zeta":"z","alpha":"a","children":[{"name":"b","type":"Link","target":"b/LOOKME.json"},{"name":"a","description":"a","children":[{"name":"c"}]},{"name":"A"}]}"#;
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    tree.insert_nodes_from_str(json_str,
+                               PathBuf::from("LOOKME.json"),
+                               None,
+                               &vec![]).unwrap();
+    assert_eq!(tree.to_string().unwrap(), json_str);
+}
+
+#[test]
+fn children_order_test() {
+    let json_str = r#"{"children":[{"name":"b"},{"name":"d","type":"Link","target":"d/LOOKME.json"},{"name":"a"},{"name":"c","children":[{"name":"x"}]}]}"#;
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    tree.insert_nodes_from_str(json_str,
+                               PathBuf::from("LOOKME.json"),
+                               None,
+                               &vec![]).unwrap();
+    let names = |string: String| {
+        let value: serde_json::Value = serde_json::from_str(&string).unwrap();
+        value["children"].as_array().unwrap().iter()
+                         .map(|child| child["name"].as_str().unwrap().to_string())
+                         .collect::<Vec<_>>()
+    };
+    assert_eq!(names(tree.to_string_with_order(ChildrenOrder::Alphabetical).unwrap()),
+               vec!["a", "b", "c", "d"]);
+    assert_eq!(names(tree.to_string_with_order(ChildrenOrder::DirectoriesFirst).unwrap()),
+               vec!["c", "d", "a", "b"]);
+}
+
+#[test]
+fn typed_properties_test() {
+    let json_str = r#"{"name":"Project","since":2019,"ratio":0.5,"deprecated":true,"tags":["io","net"],"owner":{"team":"infra","members":["a","b"]}}"#;
+    let tree = Tree::new(&TreeConfig {
+        project_name: "Project".to_string()
+    });
+    tree.insert_nodes_from_str(json_str,
+                               PathBuf::from("LOOKME.json"),
+                               None,
+                               &vec![]).unwrap();
+    assert_eq!(tree.to_string().unwrap(), json_str);
+}
@@ -0,0 +1,233 @@
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use libwispha::manipulator;
+use libwispha::serde::de;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CheckOptions {
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root JSON file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+}
+
+struct CheckConfig {
+    project_name: String,
+    file: PathBuf,
+}
+
+/// A problem found in Wispha tree, located by the file it is recorded in
+struct Problem {
+    record_file: PathBuf,
+    node_path: String,
+    message: String,
+    /// Location in the record file, if the file's content is broken
+    location: Option<de::Location>,
+}
+
+impl Problem {
+    fn from_de_error(de_error: &de::Error) -> Problem {
+        let location = de_error.location();
+        Problem {
+            record_file: location.record_file.clone(),
+            node_path: location.node_path.to_string(),
+            message: de_error.message(),
+            location: Some(location.clone())
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if let Some(location) = &self.location {
+            write!(f, "{}:{}:{}: {}: {}\n{}",
+                   self.record_file.to_str().unwrap(),
+                   location.line,
+                   location.column,
+                   self.node_path,
+                   self.message,
+                   location.snippet())
+        } else {
+            write!(f, "{}: {}: {}", self.record_file.to_str().unwrap(), self.node_path, self.message)
+        }
+    }
+}
+
+impl CheckConfig {
+    fn from_opt(opt: CheckOptions) -> Result<Self, Error> {
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        Ok(CheckConfig {
+            project_name,
+            file
+        })
+    }
+}
+
+impl CommandlineOption for CheckOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = CheckConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+
+        let problems = match tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS) {
+            Ok(_) => check_tree(&tree),
+            Err(error) => vec![Problem::from_de_error(&error)]
+        };
+
+        for problem in &problems {
+            println!("{}", problem);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(Error::ProblemsFound(problems.len())))
+        }
+    }
+}
+
+/// Resolve every link node reachable from root, collecting the problems instead of stopping at the first one.
+///
+/// A link node that fails to resolve is reported, and its subtree is skipped.
+fn check_tree(tree: &Tree) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut path_queue = VecDeque::new();
+    path_queue.push_back(NodePath::new(tree));
+
+    while let Some(node_path) = path_queue.pop_front() {
+        match tree.resolve_in_depth(&node_path, 0, &FsResolver, &crate::PRESERVED_KEYS) {
+            Ok(()) => {
+                // After resolving in depth 0, there must be a direct node at `node_path`
+                let node = tree.get_node(&node_path).unwrap();
+                let node = node.borrow();
+                for child in &node.get_direct().unwrap().children {
+                    path_queue.push_back(child.clone());
+                }
+            },
+            Err(manipulator::Error::Deserialization(de_error)) => {
+                // The target file is read successfully, but its content is broken
+                problems.push(Problem::from_de_error(&de_error));
+            },
+            Err(error) => {
+                // The node at `node_path` is the link node whose resolving failed
+                let node = tree.get_node(&node_path).unwrap();
+                let record_file = node.borrow().node_properties().record_file;
+                problems.push(Problem {
+                    record_file,
+                    node_path: node_path.to_string(),
+                    message: error.to_string(),
+                    location: None
+                });
+            }
+        }
+    }
+
+    problems
+}
+
+#[derive(Debug)]
+pub enum Error {
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    ProblemsFound(usize),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            ProblemsFound(count) => format!("Found {} problem(s) in the Wispha tree.", count),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_problems_collected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for sub_dir in &["cycle", "broken", "fine"] {
+            fs::create_dir(root.join(sub_dir)).unwrap();
+        }
+        let root_file = root.join("LOOKME.json");
+        fs::write(&root_file, r#"{"children": [
+            {"name": "missing", "type": "Link", "target": "missing/LOOKME.json"},
+            {"name": "cycle", "type": "Link", "target": "cycle/LOOKME.json"},
+            {"name": "broken", "type": "Link", "target": "broken/LOOKME.json"},
+            {"name": "fine", "type": "Link", "target": "fine/LOOKME.json"}
+        ]}"#).unwrap();
+        fs::write(root.join("cycle/LOOKME.json"), r#"{"children": [{"name": "back", "type": "Link", "target": "LOOKME.json"}]}"#).unwrap();
+        fs::write(root.join("broken/LOOKME.json"), r#"{"children": [{"description": "no name"}]}"#).unwrap();
+        fs::write(root.join("fine/LOOKME.json"), r#"{"description": "fine"}"#).unwrap();
+
+        let tree = Tree::new(&TreeConfig {
+            project_name: ".".to_string()
+        });
+        tree.insert_nodes_from_str(&fs::read_to_string(&root_file).unwrap(), root_file.clone(), None, &crate::PRESERVED_KEYS).unwrap();
+        let problems = check_tree(&tree);
+        let located = problems.iter()
+            .map(|problem| (problem.record_file.strip_prefix(root).unwrap().to_str().unwrap(), problem.node_path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(located, vec![
+            ("LOOKME.json", "/missing"),
+            ("broken/LOOKME.json", "/broken"),
+            ("cycle/LOOKME.json", "/cycle/back"),
+        ]);
+        assert!(problems[0].location.is_none());
+        assert!(problems[1].location.is_some());
+
+        let options = CheckOptions {
+            project_name: None,
+            file: Some(root_file)
+        };
+        let error = options.run().unwrap_err();
+        assert!(matches!(error.downcast_ref::<Error>(), Some(Error::ProblemsFound(3))));
+    }
+}
@@ -0,0 +1,365 @@
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use libwispha::serde::format::{self, Format};
+use libwispha::strings;
+use structopt::StructOpt;
+
+use std::path::{Path, PathBuf};
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ConvertOptions {
+    /// Format of the converted record files. For now, it's "json", "toml" or "yaml".
+    /// The format of output's extension by default, or each file keeps its format if output is not given either
+    #[structopt(long)]
+    format: Option<String>,
+
+    /// File path for the converted root record file. The root record file with the extension of the format by default
+    #[structopt(long, short)]
+    output: Option<PathBuf>,
+
+    /// Record the whole tree in the root record file, inlining every link
+    #[structopt(long, conflicts_with_all = &["split-depth", "split-threshold"])]
+    flatten: bool,
+
+    /// Record each directory within this depth in its own file, in the directory and named as the root record file.
+    /// Other directories are inlined. Root is of depth 0
+    #[structopt(long)]
+    split_depth: Option<usize>,
+
+    /// Record each directory with more than this number of nodes in its own file, in the directory and named as the root record file.
+    /// Other directories are inlined. If `--split-depth` is given too, a directory is split only if it satisfies both
+    #[structopt(long)]
+    split_threshold: Option<usize>,
+
+    /// Name for the project, used for the name of top directory. The name recorded in the root record file, or "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root record file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+}
+
+/// Which files nodes are recorded in after converting
+enum Storage {
+    /// Each node stays in its file, whose extension is changed to the format's
+    Keep,
+    Flatten,
+    Split {
+        depth: Option<usize>,
+        threshold: Option<usize>,
+    },
+}
+
+struct ConvertConfig {
+    format: Option<&'static dyn Format>,
+    output: PathBuf,
+    storage: Storage,
+    project_name: Option<String>,
+    file: PathBuf,
+}
+
+impl ConvertConfig {
+    fn from_opt(opt: ConvertOptions) -> Result<Self, Error> {
+        let current_dir = || env::current_dir().map_err(Error::CurrentDirectoryNotAvailable);
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                current_dir()?.join(file)
+            }
+        } else {
+            current_dir()?.join("LOOKME.json")
+        };
+
+        let output = if let Some(output) = opt.output {
+            if output.is_absolute() {
+                Some(output)
+            } else {
+                Some(current_dir()?.join(output))
+            }
+        } else {
+            None
+        };
+
+        let format = if let Some(format) = &opt.format {
+            Some(format::format_named(format).ok_or_else(|| Error::UnknownFormat(format.clone()))?)
+        } else {
+            None
+        };
+
+        let (format, output) = match (format, output) {
+            (Some(format), Some(output)) => {
+                if format::format_of(&output).name() != format.name() {
+                    return Err(Error::FormatMismatch((output, format.name().to_string())));
+                }
+                (Some(format), output)
+            },
+            (Some(format), None) => (Some(format), converted_file(&file, format)),
+            (None, Some(output)) => (Some(format::format_of(&output)), output),
+            (None, None) => (None, file.clone()),
+        };
+
+        let storage = if opt.flatten {
+            Storage::Flatten
+        } else if opt.split_depth.is_some() || opt.split_threshold.is_some() {
+            Storage::Split {
+                depth: opt.split_depth,
+                threshold: opt.split_threshold
+            }
+        } else {
+            Storage::Keep
+        };
+
+        Ok(ConvertConfig {
+            format,
+            output,
+            storage,
+            project_name: opt.project_name,
+            file
+        })
+    }
+}
+
+impl CommandlineOption for ConvertOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = ConvertConfig::from_opt(self)?;
+
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        let project_name = if let Some(project_name) = &config.project_name {
+            project_name.clone()
+        } else {
+            recorded_name(&node_str, &config.file).unwrap_or_else(|| ".".to_string())
+        };
+
+        let tree = Tree::new(&TreeConfig {
+            project_name
+        });
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+        let root = NodePath::new(&tree);
+        tree.resolve_in_depth(&root, usize::MAX, &FsResolver, &crate::PRESERVED_KEYS)?;
+
+        // Record files are decided before any node is moved, since directories are found by the paths of nodes
+        let mut record_files = vec![(root.clone(), config.output.clone())];
+        push_record_files(&tree, &root, 0, &config.output, &config, &mut record_files)?;
+        for (node_path, record_file) in record_files {
+            tree.set_record_file(&node_path, record_file)?;
+        }
+
+        for file in tree.save()? {
+            println!("Written {}", file.to_string_lossy());
+        }
+        Ok(())
+    }
+}
+
+// The name recorded in the upmost node of `node_str`
+fn recorded_name(node_str: &str, record_file: &Path) -> Option<String> {
+    if let PropertyValue::Object(map) = format::format_of(record_file).parse(node_str).ok()? {
+        if let Some(PropertyValue::String(name)) = map.get(strings::NAME) {
+            return Some(name.clone());
+        }
+    }
+    None
+}
+
+// `record_file` with the extension of `format`, unless it's already in `format`
+fn converted_file(record_file: &Path, format: &dyn Format) -> PathBuf {
+    if format::format_of(record_file).name() == format.name() {
+        record_file.to_path_buf()
+    } else {
+        record_file.with_extension(format.extensions()[0])
+    }
+}
+
+// Push the new record files of the descendants of the node at `node_path`, which is of `depth` and recorded in `record_file`
+fn push_record_files(tree: &Tree,
+                     node_path: &NodePath,
+                     depth: usize,
+                     record_file: &Path,
+                     config: &ConvertConfig,
+                     record_files: &mut Vec<(NodePath, PathBuf)>) -> Result<(), Box<dyn error::Error>> {
+    // After resolving in depth, every node is a direct node
+    let node = tree.get_node(node_path).unwrap();
+    let children = node.borrow().get_direct().unwrap().children.clone();
+    for child in &children {
+        let child_node = tree.get_node(child).unwrap();
+        let (old_record_file, is_dir) = {
+            let child_node = child_node.borrow();
+            let direct_node = child_node.get_direct().unwrap();
+            (direct_node.node_properties.record_file.clone(), !direct_node.children.is_empty())
+        };
+        let child_record_file = match &config.storage {
+            Storage::Keep => {
+                if old_record_file == config.file {
+                    config.output.clone()
+                } else if let Some(format) = config.format {
+                    converted_file(&old_record_file, format)
+                } else {
+                    old_record_file
+                }
+            },
+            Storage::Flatten => record_file.to_path_buf(),
+            Storage::Split { depth: split_depth, threshold } => {
+                let is_split = is_dir
+                    && split_depth.map(|split_depth| depth < split_depth).unwrap_or(true)
+                    && threshold.map(|threshold| subtree_size(tree, child) > threshold).unwrap_or(true);
+                if is_split {
+                    tree.get_path_buf(child)?.join(config.output.file_name().unwrap())
+                } else {
+                    record_file.to_path_buf()
+                }
+            }
+        };
+        push_record_files(tree, child, depth + 1, &child_record_file, config, record_files)?;
+        record_files.push((child.clone(), child_record_file));
+    }
+    Ok(())
+}
+
+// Number of nodes in the subtree at `node_path`, including itself
+fn subtree_size(tree: &Tree, node_path: &NodePath) -> usize {
+    let node = tree.get_node(node_path).unwrap();
+    let children = node.borrow().get_direct().unwrap().children.clone();
+    1 + children.iter().map(|child| subtree_size(tree, child)).sum::<usize>()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    UnknownFormat(String),
+    /// The output file and the format it's expected to be in
+    FormatMismatch((PathBuf, String)),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            UnknownFormat(format) => format!("Unknown format {}.", format),
+            FormatMismatch((output, format)) => format!("Output {} is not of format {}.", output.to_str().unwrap(), format),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Tree {
+        let tree = Tree::new(&TreeConfig {
+            project_name: "Project".to_string()
+        });
+        let json_str = r#"{"children": [
+            {"name": "src", "children": [{"name": "lib", "children": [{"name": "a.rs"}]}, {"name": "main.rs"}]},
+            {"name": "doc", "children": [{"name": "guide.md"}]},
+            {"name": "README.md"}
+        ]}"#;
+        tree.insert_nodes_from_str(json_str, PathBuf::from("/project/LOOKME.json"), None, &crate::PRESERVED_KEYS).unwrap();
+        tree
+    }
+
+    // New record files of the nodes other than root, relative to the project
+    fn record_files(storage: Storage) -> Vec<(String, String)> {
+        let tree = tree();
+        let config = ConvertConfig {
+            format: format::format_named("yaml"),
+            output: PathBuf::from("/project/LOOKME.yaml"),
+            storage,
+            project_name: None,
+            file: PathBuf::from("/project/LOOKME.json")
+        };
+        let mut record_files = vec![];
+        push_record_files(&tree, &NodePath::new(&tree), 0, &config.output, &config, &mut record_files).unwrap();
+        let mut record_files = record_files.into_iter().map(|(node_path, record_file)| {
+            (node_path.to_string(), record_file.strip_prefix("/project").unwrap().to_string_lossy().to_string())
+        }).collect::<Vec<_>>();
+        record_files.sort();
+        record_files
+    }
+
+    fn expected(record_files: &[(&str, &str)]) -> Vec<(String, String)> {
+        record_files.iter().map(|(node_path, record_file)| (node_path.to_string(), record_file.to_string())).collect()
+    }
+
+    #[test]
+    fn flatten() {
+        assert_eq!(record_files(Storage::Flatten), expected(&[
+            ("/README.md", "LOOKME.yaml"),
+            ("/doc", "LOOKME.yaml"),
+            ("/doc/guide.md", "LOOKME.yaml"),
+            ("/src", "LOOKME.yaml"),
+            ("/src/lib", "LOOKME.yaml"),
+            ("/src/lib/a.rs", "LOOKME.yaml"),
+            ("/src/main.rs", "LOOKME.yaml"),
+        ]));
+    }
+
+    #[test]
+    fn split_by_depth() {
+        assert_eq!(record_files(Storage::Split { depth: Some(1), threshold: None }), expected(&[
+            ("/README.md", "LOOKME.yaml"),
+            ("/doc", "doc/LOOKME.yaml"),
+            ("/doc/guide.md", "doc/LOOKME.yaml"),
+            ("/src", "src/LOOKME.yaml"),
+            ("/src/lib", "src/LOOKME.yaml"),
+            ("/src/lib/a.rs", "src/LOOKME.yaml"),
+            ("/src/main.rs", "src/LOOKME.yaml"),
+        ]));
+    }
+
+    #[test]
+    fn split_by_threshold() {
+        let tree = tree();
+        assert_eq!(subtree_size(&tree, &NodePath::new(&tree)), 8);
+        assert_eq!(subtree_size(&tree, &NodePath::from("/src/lib", &tree).unwrap()), 2);
+        assert_eq!(record_files(Storage::Split { depth: None, threshold: Some(1) }), expected(&[
+            ("/README.md", "LOOKME.yaml"),
+            ("/doc", "doc/LOOKME.yaml"),
+            ("/doc/guide.md", "doc/LOOKME.yaml"),
+            ("/src", "src/LOOKME.yaml"),
+            ("/src/lib", "src/lib/LOOKME.yaml"),
+            ("/src/lib/a.rs", "src/lib/LOOKME.yaml"),
+            ("/src/main.rs", "src/LOOKME.yaml"),
+        ]));
+        // Both conditions must be satisfied
+        assert_eq!(record_files(Storage::Split { depth: Some(1), threshold: Some(2) }), expected(&[
+            ("/README.md", "LOOKME.yaml"),
+            ("/doc", "LOOKME.yaml"),
+            ("/doc/guide.md", "LOOKME.yaml"),
+            ("/src", "src/LOOKME.yaml"),
+            ("/src/lib", "src/LOOKME.yaml"),
+            ("/src/lib/a.rs", "src/LOOKME.yaml"),
+            ("/src/main.rs", "src/LOOKME.yaml"),
+        ]));
+    }
+
+    #[test]
+    fn keep_files() {
+        assert!(record_files(Storage::Keep).iter().all(|(_, record_file)| record_file == "LOOKME.yaml"));
+        let yaml = format::format_named("yaml").unwrap();
+        assert_eq!(converted_file(Path::new("/a/LOOKME.toml"), yaml), PathBuf::from("/a/LOOKME.yaml"));
+        assert_eq!(converted_file(Path::new("/a/LOOKME.yml"), yaml), PathBuf::from("/a/LOOKME.yml"));
+    }
+
+    #[test]
+    fn name_recorded_in_root_file() {
+        assert_eq!(recorded_name("name = \"wispha\"\n", Path::new("LOOKME.toml")), Some("wispha".to_string()));
+        assert_eq!(recorded_name("{\"children\": []}", Path::new("LOOKME.json")), None);
+    }
+}
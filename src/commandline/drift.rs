@@ -0,0 +1,319 @@
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use libwispha::ignorer::Ignorer;
+use serde_json::json;
+use structopt::StructOpt;
+
+use std::path::{Path, PathBuf};
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DriftOptions {
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root JSON file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+
+    /// Output format. For now, it's "text" or "json". "text" by default
+    #[structopt(long)]
+    format: Option<String>,
+}
+
+enum DriftFormat {
+    Text,
+    Json,
+}
+
+struct DriftConfig {
+    project_name: String,
+    file: PathBuf,
+    format: DriftFormat,
+}
+
+/// A node recorded in Wispha tree, whose path does not exist in filesystem
+struct MissingEntry {
+    node_path: String,
+    path: PathBuf,
+}
+
+/// A file or directory in filesystem, which has no node in a documented directory.
+///
+/// A documented directory is a node with at least one child. Files ignored by `.gitignore` or `.wisphaignore` are not reported.
+struct UndocumentedEntry {
+    node_path: String,
+    path: PathBuf,
+}
+
+struct DriftReport {
+    missing: Vec<MissingEntry>,
+    undocumented: Vec<UndocumentedEntry>,
+}
+
+impl DriftConfig {
+    fn from_opt(opt: DriftOptions) -> Result<Self, Error> {
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        let format = if let Some(format) = opt.format {
+            match format.as_str() {
+                "text" => DriftFormat::Text,
+                "json" => DriftFormat::Json,
+                _ => return Err(Error::UnknownFormat(format))
+            }
+        } else {
+            DriftFormat::Text
+        };
+
+        Ok(DriftConfig {
+            project_name,
+            file,
+            format
+        })
+    }
+}
+
+impl CommandlineOption for DriftOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = DriftConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+        let root_path = NodePath::new(&tree);
+        tree.resolve_in_depth(&root_path, usize::MAX, &FsResolver, &crate::PRESERVED_KEYS)?;
+
+        let root_dir = tree.get_path_buf(&root_path)?;
+        let ignorer = Ignorer::new(&root_dir, &[format!("{}/", crate::WISPHA_DIR)])?;
+        let report = drift_report(&tree, &ignorer)?;
+
+        let output = match config.format {
+            DriftFormat::Text => report.to_text(),
+            DriftFormat::Json => report.to_json(&root_dir),
+        };
+        println!("{}", output);
+        Ok(())
+    }
+}
+
+fn drift_report(tree: &Tree, ignorer: &Ignorer) -> Result<DriftReport, Box<dyn error::Error>> {
+    let mut missing = vec![];
+    let mut undocumented = vec![];
+
+    // All the record files, which are not expected to be documented
+    let mut record_files = HashSet::new();
+    let mut nodes = vec![];
+
+    let mut path_queue = VecDeque::new();
+    path_queue.push_back(NodePath::new(tree));
+    while let Some(node_path) = path_queue.pop_front() {
+        // After resolving in depth, there must be a direct node at `node_path`
+        let node = tree.get_node(&node_path).unwrap();
+        let node = node.borrow();
+        let direct_node = node.get_direct().unwrap();
+        record_files.insert(direct_node.node_properties.record_file.clone());
+        for child in &direct_node.children {
+            path_queue.push_back(child.clone());
+        }
+        nodes.push((node_path.clone(), direct_node.children.clone()));
+    }
+
+    for (node_path, children) in nodes {
+        let path = tree.get_path_buf(&node_path)?;
+        if !path.exists() {
+            missing.push(MissingEntry {
+                node_path: node_path.to_string(),
+                path
+            });
+            continue;
+        }
+        if children.is_empty() || !path.is_dir() {
+            continue;
+        }
+        let documented_names = children.iter()
+            .filter_map(|child| child.name())
+            .collect::<HashSet<_>>();
+        let mut entries = fs::read_dir(&path)
+            .map_err(|io_error| Error::CannotRead((path.clone(), io_error)))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|entry_path| !record_files.contains(entry_path))
+            .collect::<Vec<_>>();
+        entries.sort();
+        for entry_path in entries {
+            let name = entry_path.file_name().unwrap().to_string_lossy().to_string();
+            if !documented_names.contains(&name) && !ignorer.is_ignored(&entry_path, entry_path.is_dir())? {
+                undocumented.push(UndocumentedEntry {
+                    node_path: node_path.push(name).to_string(),
+                    path: entry_path
+                });
+            }
+        }
+    }
+
+    Ok(DriftReport {
+        missing,
+        undocumented
+    })
+}
+
+// Display `path` relative to `root_dir` if possible
+fn relative_path(path: &Path, root_dir: &Path) -> String {
+    path.strip_prefix(root_dir).unwrap_or(path).to_string_lossy().to_string()
+}
+
+impl DriftReport {
+    fn to_text(&self) -> String {
+        if self.missing.is_empty() && self.undocumented.is_empty() {
+            return String::from("No drift found.");
+        }
+        let mut lines = vec![];
+        if !self.missing.is_empty() {
+            lines.push(String::from("Documented but missing:"));
+            for entry in &self.missing {
+                lines.push(format!("    {}", entry.node_path));
+            }
+        }
+        if !self.undocumented.is_empty() {
+            lines.push(String::from("Present but undocumented:"));
+            for entry in &self.undocumented {
+                lines.push(format!("    {}", entry.node_path));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn to_json(&self, root_dir: &Path) -> String {
+        let missing = self.missing.iter().map(|entry| json!({
+            "node_path": entry.node_path,
+            "path": relative_path(&entry.path, root_dir)
+        })).collect::<Vec<_>>();
+        let undocumented = self.undocumented.iter().map(|entry| json!({
+            "node_path": entry.node_path,
+            "path": relative_path(&entry.path, root_dir)
+        })).collect::<Vec<_>>();
+        serde_json::to_string_pretty(&json!({
+            "missing": missing,
+            "undocumented": undocumented
+        })).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    CannotRead((PathBuf, std::io::Error)),
+    UnknownFormat(String),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            CannotRead((path, io_error)) => format!("Cannot open {}: {}", path.to_str().unwrap(), io_error),
+            UnknownFormat(format) => format!("Unknown format {}.", format),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DriftReport {
+        DriftReport {
+            missing: vec![MissingEntry {
+                node_path: "/gone".to_string(),
+                path: PathBuf::from("/project/gone")
+            }],
+            undocumented: vec![UndocumentedEntry {
+                node_path: "/src/new.rs".to_string(),
+                path: PathBuf::from("/project/src/new.rs")
+            }]
+        }
+    }
+
+    #[test]
+    fn text_report() {
+        assert_eq!(report().to_text(), "Documented but missing:\n    /gone\nPresent but undocumented:\n    /src/new.rs");
+        let report = DriftReport {
+            missing: vec![],
+            undocumented: vec![]
+        };
+        assert_eq!(report.to_text(), "No drift found.");
+    }
+
+    #[test]
+    fn json_report() {
+        let report: serde_json::Value = serde_json::from_str(&report().to_json(Path::new("/project"))).unwrap();
+        assert_eq!(report, json!({
+            "missing": [{"node_path": "/gone", "path": "gone"}],
+            "undocumented": [{"node_path": "/src/new.rs", "path": "src/new.rs"}]
+        }));
+    }
+
+    #[test]
+    fn drift_in_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("doc")).unwrap();
+        for file in &["src/main.rs", "src/new.rs", "src/main.rs.swp", "doc/guide.md"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        fs::write(root.join(".wisphaignore"), "*.swp\n").unwrap();
+        let root_file = root.join("LOOKME.json");
+        fs::write(&root_file, r#"{"children": [
+            {"name": ".wisphaignore"},
+            {"name": "src", "children": [{"name": "main.rs"}, {"name": "gone.rs"}]},
+            {"name": "doc"}
+        ]}"#).unwrap();
+
+        let tree = Tree::new(&TreeConfig {
+            project_name: ".".to_string()
+        });
+        tree.insert_nodes_from_str(&fs::read_to_string(&root_file).unwrap(), root_file, None, &crate::PRESERVED_KEYS).unwrap();
+        let ignorer = Ignorer::new(root, &[]).unwrap();
+        let report = drift_report(&tree, &ignorer).unwrap();
+        // `doc` has no children recorded, so its files are not expected to be documented
+        assert_eq!(report.missing.iter().map(|entry| entry.node_path.as_str()).collect::<Vec<_>>(), vec!["/src/gone.rs"]);
+        assert_eq!(report.undocumented.iter().map(|entry| entry.node_path.as_str()).collect::<Vec<_>>(), vec!["/src/new.rs"]);
+    }
+}
@@ -0,0 +1,144 @@
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+// The property shown for each ancestor
+static DESCRIPTION: &str = "description";
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ExplainOptions {
+    /// Path for the file or directory to be explained, absolute or relative to the current directory
+    path: PathBuf,
+
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root JSON file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+}
+
+struct ExplainConfig {
+    path: PathBuf,
+    project_name: String,
+    file: PathBuf,
+}
+
+impl ExplainConfig {
+    fn from_opt(opt: ExplainOptions) -> Result<Self, Error> {
+        let path = opt.path;
+
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        Ok(ExplainConfig {
+            path,
+            project_name,
+            file
+        })
+    }
+}
+
+impl CommandlineOption for ExplainOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = ExplainConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+        let node_path = tree.node_path_of(&config.path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        println!("{}", explanation(&tree, &node_path));
+        Ok(())
+    }
+}
+
+// The node path, the breadcrumb of ancestors with their descriptions, and the node's properties.
+// `node_path` and its ancestors must be direct nodes
+fn explanation(tree: &Tree, node_path: &NodePath) -> String {
+    let mut ancestors = vec![];
+    let mut ancestor = node_path.parent();
+    while let Some(ancestor_path) = ancestor {
+        let node = tree.get_node(&ancestor_path).unwrap();
+        let description = node.borrow().get_direct().unwrap()
+            .get_property(DESCRIPTION)
+            .map(|description| description.to_string());
+        ancestor = ancestor_path.parent();
+        ancestors.push((ancestor_path.to_string(), description));
+    }
+    ancestors.reverse();
+
+    let mut lines = vec![node_path.to_string()];
+    if !ancestors.is_empty() {
+        lines.push(String::from("Ancestors:"));
+        let max_len = ancestors.iter().map(|(path, _)| path.chars().count()).max().unwrap_or(0);
+        for (path, description) in ancestors {
+            if let Some(description) = description {
+                let remain = max_len + 4 - path.chars().count();
+                lines.push(format!("    {}{}{}", path, " ".repeat(remain), description));
+            } else {
+                lines.push(format!("    {}", path));
+            }
+        }
+    }
+    let node = tree.get_node(node_path).unwrap();
+    let node = node.borrow();
+    let properties = &node.get_direct().unwrap().properties;
+    if !properties.is_empty() {
+        lines.push(String::from("Properties:"));
+        for (key, value) in properties {
+            lines.push(format!("    {}: {}", key, value));
+        }
+    }
+    lines.join("\n")
+}
+
+#[derive(Debug)]
+pub enum Error {
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+        };
+        write!(f, "{}", message)
+    }
+}
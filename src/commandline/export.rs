@@ -0,0 +1,372 @@
+use crate::layouter::{self, LayoutEntry};
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ExportOptions {
+    /// Graph format. For now, it's "dot" or "mermaid". "dot" by default
+    #[structopt(long)]
+    format: Option<String>,
+
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// Node path for the node to be exported at top level. "/" by default
+    #[structopt(long, short)]
+    path: Option<String>,
+
+    /// List of keys to be displayed in node labels. A nested value can be addressed by a dotted path like `owner.team`. Empty list by default
+    #[structopt(long, short, use_delimiter = true)]
+    keys: Option<Vec<String>>,
+
+    /// List of keys to be displayed in node tooltips. Empty list by default
+    #[structopt(long, use_delimiter = true)]
+    tooltip_keys: Option<Vec<String>>,
+
+    /// File path for the project's root record file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+
+    /// Graph depth. 3 by default
+    #[structopt(long, short)]
+    depth: Option<usize>,
+}
+
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+struct ExportConfig {
+    format: GraphFormat,
+    project_name: String,
+    path: String,
+    keys: Vec<String>,
+    tooltip_keys: Vec<String>,
+    file: PathBuf,
+    depth: usize,
+}
+
+impl ExportConfig {
+    fn from_opt(opt: ExportOptions) -> Result<Self, Error> {
+        let format = match opt.format.as_deref() {
+            None | Some("dot") => GraphFormat::Dot,
+            Some("mermaid") => GraphFormat::Mermaid,
+            Some(format) => return Err(Error::UnknownFormat(format.to_string()))
+        };
+
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let path = if let Some(path) = opt.path {
+            if path.starts_with('/') {
+                path
+            } else {
+                return Err(Error::NodePathMustBeAbsolute(path));
+            }
+        } else {
+            "/".to_string()
+        };
+
+        let keys = opt.keys.unwrap_or_default();
+
+        let tooltip_keys = opt.tooltip_keys.unwrap_or_default();
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        let depth = opt.depth.unwrap_or(3);
+
+        Ok(ExportConfig {
+            format,
+            project_name,
+            path,
+            keys,
+            tooltip_keys,
+            file,
+            depth
+        })
+    }
+}
+
+impl CommandlineOption for ExportOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = ExportConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+        let node_path = NodePath::from(&config.path, &tree)?;
+
+        let mut keys = config.keys.clone();
+        keys.extend(config.tooltip_keys.iter().filter(|key| !config.keys.contains(key)).cloned());
+        let entries = layouter::walk(&tree, &node_path, config.depth, &keys)?;
+        let nodes = entries.iter().map(|entry| GraphNode::new(entry, &config)).collect::<Vec<_>>();
+
+        let graph = match config.format {
+            GraphFormat::Dot => dot(&nodes),
+            GraphFormat::Mermaid => mermaid(&nodes),
+        };
+        println!("{}", graph);
+        Ok(())
+    }
+}
+
+/// A node in the graph. Nodes with displayed children are drawn as clusters
+struct GraphNode<'a> {
+    entry: &'a LayoutEntry,
+    /// Lines of the label, the first of which is the name
+    label: Vec<String>,
+    tooltip: Vec<String>,
+}
+
+impl<'a> GraphNode<'a> {
+    fn new(entry: &'a LayoutEntry, config: &ExportConfig) -> Self {
+        let lines = |keys: &[String]| entry.properties.iter()
+            .filter(|(key, _)| keys.contains(key))
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect::<Vec<_>>();
+        let mut label = vec![entry.name.clone()];
+        label.extend(lines(&config.keys));
+        GraphNode {
+            entry,
+            label,
+            tooltip: lines(&config.tooltip_keys)
+        }
+    }
+}
+
+// Nodes are given ids by their order, and clusters are closed when the walk leaves them, as in the html layout
+fn dot(nodes: &[GraphNode]) -> String {
+    let mut lines = vec![
+        "digraph wispha {".to_string(),
+        "    rankdir=LR;".to_string(),
+        "    node [shape=box];".to_string(),
+    ];
+    // Depths of the clusters not closed yet
+    let mut opened: Vec<usize> = vec![];
+    for (id, node) in nodes.iter().enumerate() {
+        while opened.last().map(|&depth| depth >= node.entry.depth).unwrap_or(false) {
+            opened.pop();
+            lines.push(format!("{}}}", "    ".repeat(opened.len() + 1)));
+        }
+        let indent = "    ".repeat(opened.len() + 1);
+        let label = dot_string(&node.label.join("\n"));
+        let tooltip = dot_string(&node.tooltip.join("\n"));
+        if node.entry.has_children {
+            lines.push(format!("{}subgraph cluster_{} {{", indent, id));
+            lines.push(format!("{}    label={};", indent, label));
+            if !node.tooltip.is_empty() {
+                lines.push(format!("{}    tooltip={};", indent, tooltip));
+            }
+            if node.entry.linked {
+                lines.push(format!("{}    style=dashed;", indent));
+            }
+            opened.push(node.entry.depth);
+        } else {
+            let mut attributes = vec![format!("label={}", label)];
+            if !node.tooltip.is_empty() {
+                attributes.push(format!("tooltip={}", tooltip));
+            }
+            if node.entry.linked {
+                attributes.push("style=dashed".to_string());
+            }
+            lines.push(format!("{}n{} [{}];", indent, id, attributes.join(", ")));
+        }
+    }
+    while opened.pop().is_some() {
+        lines.push(format!("{}}}", "    ".repeat(opened.len() + 1)));
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn mermaid(nodes: &[GraphNode]) -> String {
+    let mut lines = vec!["flowchart LR".to_string()];
+    let mut opened: Vec<usize> = vec![];
+    let mut linked = vec![];
+    let mut tooltips = vec![];
+    for (id, node) in nodes.iter().enumerate() {
+        while opened.last().map(|&depth| depth >= node.entry.depth).unwrap_or(false) {
+            opened.pop();
+            lines.push(format!("{}end", "    ".repeat(opened.len() + 1)));
+        }
+        let indent = "    ".repeat(opened.len() + 1);
+        let label = node.label.iter().map(|line| mermaid_string(line)).collect::<Vec<_>>().join("<br/>");
+        if node.entry.has_children {
+            lines.push(format!("{}subgraph n{} [\"{}\"]", indent, id, label));
+            opened.push(node.entry.depth);
+        } else {
+            lines.push(format!("{}n{}[\"{}\"]", indent, id, label));
+        }
+        if node.entry.linked {
+            linked.push(format!("n{}", id));
+        }
+        // Mermaid shows tooltips of clickable nodes only, and subgraphs can't be clicked
+        if !node.entry.has_children && !node.tooltip.is_empty() {
+            let tooltip = node.tooltip.iter().map(|line| mermaid_string(line)).collect::<Vec<_>>().join(", ");
+            tooltips.push(format!("    click n{} href \"#\" \"{}\"", id, tooltip));
+        }
+    }
+    while opened.pop().is_some() {
+        lines.push(format!("{}end", "    ".repeat(opened.len() + 1)));
+    }
+    lines.extend(tooltips);
+    if !linked.is_empty() {
+        lines.push("    classDef link stroke-dasharray: 5 5;".to_string());
+        lines.push(format!("    class {} link;", linked.join(",")));
+    }
+    lines.join("\n")
+}
+
+// `text` as a quoted DOT string, where line breaks are kept
+fn dot_string(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+// `text` to be put in a quoted Mermaid string, which can't contain quotes or line breaks
+fn mermaid_string(text: &str) -> String {
+    text.replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', " ")
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NodePathMustBeAbsolute(String),
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    UnknownFormat(String),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path),
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            UnknownFormat(format) => format!("Unknown format {}.", format),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keys: &[&str], tooltip_keys: &[&str]) -> ExportConfig {
+        ExportConfig {
+            format: GraphFormat::Dot,
+            project_name: "Project".to_string(),
+            path: "/".to_string(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            tooltip_keys: tooltip_keys.iter().map(|key| key.to_string()).collect(),
+            file: PathBuf::from("/project/LOOKME.json"),
+            depth: 3
+        }
+    }
+
+    // Entries of a project, where `/src/lib` is recorded in another file
+    fn entries() -> Vec<LayoutEntry> {
+        let tree = Tree::new(&TreeConfig {
+            project_name: "Project".to_string()
+        });
+        let json_str = r#"{"children": [
+            {"name": "src", "description": "sources", "children": [
+                {"name": "main.rs", "description": "say \"hi\"\nand <exit>", "owner": "a\\b"},
+                {"name": "lib", "owner": "core & infra"}
+            ]},
+            {"name": "README.md"}
+        ]}"#;
+        tree.insert_nodes_from_str(json_str, PathBuf::from("/project/LOOKME.json"), None, &crate::PRESERVED_KEYS).unwrap();
+        let keys = vec!["description".to_string(), "owner".to_string()];
+        let mut entries = layouter::walk(&tree, &NodePath::new(&tree), 3, &keys).unwrap();
+        entries[3].linked = true;
+        entries
+    }
+
+    fn nodes<'a>(entries: &'a [LayoutEntry], config: &ExportConfig) -> Vec<GraphNode<'a>> {
+        entries.iter().map(|entry| GraphNode::new(entry, config)).collect()
+    }
+
+    #[test]
+    fn escaped_strings() {
+        assert_eq!(dot_string("say \"hi\"\nto a\\b"), r#""say \"hi\"\nto a\\b""#);
+        assert_eq!(mermaid_string("say \"hi\"\nto <a & b>"), "say #quot;hi#quot; to #lt;a #amp; b#gt;");
+    }
+
+    #[test]
+    fn dot_graph() {
+        let entries = entries();
+        let config = config(&["description"], &["owner"]);
+        assert_eq!(dot(&nodes(&entries, &config)), r#"digraph wispha {
+    rankdir=LR;
+    node [shape=box];
+    subgraph cluster_0 {
+        label="Project";
+        subgraph cluster_1 {
+            label="src\ndescription: sources";
+            n2 [label="main.rs\ndescription: say \"hi\"\nand <exit>", tooltip="owner: a\\b"];
+            n3 [label="lib", tooltip="owner: core & infra", style=dashed];
+        }
+        n4 [label="README.md"];
+    }
+}"#);
+    }
+
+    #[test]
+    fn mermaid_graph() {
+        let entries = entries();
+        let config = config(&["description"], &["owner"]);
+        assert_eq!(mermaid(&nodes(&entries, &config)), r##"flowchart LR
+    subgraph n0 ["Project"]
+        subgraph n1 ["src<br/>description: sources"]
+            n2["main.rs<br/>description: say #quot;hi#quot; and #lt;exit#gt;"]
+            n3["lib"]
+        end
+        n4["README.md"]
+    end
+    click n2 href "#" "owner: a\b"
+    click n3 href "#" "owner: core #amp; infra"
+    classDef link stroke-dasharray: 5 5;
+    class n3 link;"##);
+    }
+}
@@ -1,8 +1,12 @@
 use libwispha::core::*;
+use libwispha::serde::de;
+use libwispha::serde::format;
+use libwispha::ignorer::{self, Ignorer};
+use libwispha::strings;
 use crate::commandline::CommandlineOption;
 
-use serde_json;
 use structopt::StructOpt;
+use indexmap::IndexMap;
 
 use std::error;
 use std::fmt;
@@ -12,10 +16,17 @@ use std::env;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::path::PathBuf;
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
 
 type Result<T> = std::result::Result<T, Error>;
 
+// node path, directory path and record file of the child directories to be generated in their own files
+type ChildDirs = Vec<(NodePath, PathBuf, PathBuf)>;
+
+// The key and value to mark a node whose file disappeared when updating
+static STATUS: &str = "status";
+static MISSING: &str = "missing";
+
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub struct GenerateOptions {
@@ -23,14 +34,42 @@ pub struct GenerateOptions {
     #[structopt(long, short)]
     path: Option<PathBuf>,
 
-    /// Default JSON file name. "LOOKME.json" by default
+    /// Default record file name. "LOOKME" with the extension of the format by default
     #[structopt(long, short = "n")]
     file_name: Option<String>,
+
+    /// Format of record files. For now, it's "json", "toml" or "yaml". The format of the file name's extension by default,
+    /// or "json" if the file name is not given either
+    #[structopt(long)]
+    format: Option<String>,
+
+    /// Merge with existing record files instead of overwriting them. Existing properties are kept,
+    /// new files are added, and nodes whose files disappeared are marked with `"status": "missing"`
+    #[structopt(long, short)]
+    update: bool,
+
+    /// When updating, remove nodes whose files disappeared instead of marking them
+    #[structopt(long, requires = "update")]
+    prune: bool,
+
+    /// Order of children. For now, it's "as-authored", "alphabetical" or "directories-first". "as-authored" by default,
+    /// which keeps the order of existing nodes when updating, and appends new files alphabetically
+    #[structopt(long, short)]
+    order: Option<String>,
+
+    /// Gitignore-style pattern of files not to be generated, relative to project path. Can be given multiple times.
+    /// Files ignored by `.gitignore` and `.wisphaignore` are not generated either
+    #[structopt(long, short = "e", number_of_values = 1)]
+    exclude: Vec<String>,
 }
 
 struct GenerateConfig {
     path: PathBuf,
-    file_name: String
+    file_name: String,
+    update: bool,
+    prune: bool,
+    order: ChildrenOrder,
+    exclude: Vec<String>,
 }
 
 impl GenerateConfig {
@@ -40,23 +79,53 @@ impl GenerateConfig {
                 path
             } else {
                 env::current_dir()
-                    .map_err(|io_error| Error::CurrentDirectoryNotAvailable(io_error))?
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
                     .join(path)
             }
         } else {
             env::current_dir()
-                .map_err(|io_error| Error::CurrentDirectoryNotAvailable(io_error))?
+                .map_err(Error::CurrentDirectoryNotAvailable)?
         };
 
-        let file_name = if let Some(file_name) = opt.file_name {
-            file_name
+        let format = if let Some(format) = &opt.format {
+            Some(format::format_named(format).ok_or_else(|| Error::UnknownFormat(format.clone()))?)
         } else {
-            String::from("LOOKME.json")
+            None
+        };
+
+        // Record files are read in the format of their extension, so the file name must agree with the format
+        let file_name = match (opt.file_name, format) {
+            (Some(file_name), Some(format)) => {
+                if format::format_of(file_name.as_ref()).name() != format.name() {
+                    return Err(Error::FormatMismatch((file_name, format.name().to_string())));
+                }
+                file_name
+            },
+            (Some(file_name), None) => file_name,
+            (None, format) => {
+                let format = format.unwrap_or(&format::Json);
+                format!("LOOKME.{}", format.extensions()[0])
+            }
+        };
+
+        let order = if let Some(order) = opt.order {
+            match order.as_str() {
+                "as-authored" => ChildrenOrder::AsAuthored,
+                "alphabetical" => ChildrenOrder::Alphabetical,
+                "directories-first" => ChildrenOrder::DirectoriesFirst,
+                _ => return Err(Error::UnknownOrder(order))
+            }
+        } else {
+            ChildrenOrder::AsAuthored
         };
 
         Ok(GenerateConfig {
             path,
-            file_name
+            file_name,
+            update: opt.update,
+            prune: opt.prune,
+            order,
+            exclude: opt.exclude,
         })
     }
 }
@@ -84,16 +153,48 @@ fn generate_file(config: GenerateConfig) -> Result<()> {
     });
 
     let root_path = NodePath::new(&tree);
+    let root_record_file = root.join(&config.file_name);
+    let mut excludes = vec![format!("{}/", crate::WISPHA_DIR)];
+    excludes.extend(config.exclude.iter().cloned());
+    let ignorer = Ignorer::new(&root, &excludes).map_err(Error::IgnoreFailed)?;
 
     let mut path_queue = VecDeque::new();
-    path_queue.push_front((root_path, root));
-
-    while let Some((node_path, path)) = path_queue.pop_front() {
-        let (node,children_paths) = generate_direct_node(&tree, node_path, path.clone(), &config)?;
-        let json_file = serde_json::to_string_pretty(&node).unwrap();
-        let file_path = node.borrow().node_properties().record_file.clone();
-        fs::write(&file_path, json_file)
-            .or_else(|io_error| Err(Error::CannotWrite((file_path.clone(), io_error))))?;
+    path_queue.push_front((root_path, root, root_record_file));
+
+    while let Some((node_path, path, record_file)) = path_queue.pop_front() {
+        let (existing_tree, existing_content) = if config.update {
+            load_existing(&record_file, &tree.config())?
+                .map(|(existing_tree, existing_content)| (Some(existing_tree), Some(existing_content)))
+                .unwrap_or((None, None))
+        } else {
+            (None, None)
+        };
+        let existing_root = existing_tree.as_ref().and_then(|existing_tree| existing_tree.root());
+        let existing_root = existing_root.as_ref().map(|existing_root| existing_root.borrow());
+        let existing = match (&existing_tree, &existing_root) {
+            (Some(existing_tree), Some(existing_root)) => {
+                if let Some(existing_node) = existing_root.get_direct() {
+                    Some((existing_tree, existing_node))
+                } else {
+                    // The whole file is a link to another file, which is left as it is
+                    continue;
+                }
+            },
+            _ => None
+        };
+        let (node, children_paths) = generate_direct_node(&tree, node_path, path.clone(), &record_file, existing, &ignorer, &config)?;
+        // An existing record file may be in another format, and its comments are kept if the format can
+        let format = format::format_of(&record_file);
+        let mut value = format::to_property_value(&*node.borrow())
+            .map_err(|error| Error::CannotSerialize((record_file.clone(), Box::new(error))))?;
+        let content = if let Some(existing_content) = &existing_content {
+            keep_recorded_name(&mut value, existing_content, format);
+            format.rewrite(existing_content, &value)
+        } else {
+            format.write(&value)
+        }.map_err(|error| Error::CannotSerialize((record_file.clone(), error)))?;
+        fs::write(&record_file, content)
+            .map_err(|io_error| Error::CannotWrite((record_file.clone(), io_error)))?;
         for child_path in children_paths {
             path_queue.push_back(child_path);
         }
@@ -102,61 +203,192 @@ fn generate_file(config: GenerateConfig) -> Result<()> {
     Ok(())
 }
 
+// Since the name of the upmost node in a file is given by its parent, the name recorded in `existing_content` is kept as it is,
+// and no name is written if it records none
+fn keep_recorded_name(value: &mut PropertyValue, existing_content: &str, format: &dyn format::Format) {
+    let recorded_name = match format.parse(existing_content) {
+        Ok(PropertyValue::Object(existing)) => existing.get(strings::NAME).cloned(),
+        _ => None
+    };
+    if let PropertyValue::Object(map) = value {
+        if let Some(recorded_name) = recorded_name {
+            map.insert(strings::NAME.to_string(), recorded_name);
+        } else {
+            map.shift_remove(strings::NAME);
+        }
+    }
+}
+
+// Load the existing `record_file` into a separate tree, with the content of the file. If `record_file` does not exist, return `None`
+fn load_existing(record_file: &PathBuf, tree_config: &TreeConfig) -> Result<Option<(Tree, String)>> {
+    if !record_file.is_file() {
+        return Ok(None);
+    }
+    let node_str = fs::read_to_string(record_file)
+        .map_err(|io_error| Error::CannotRead((record_file.clone(), io_error)))?;
+    let existing_tree = Tree::new(tree_config);
+    existing_tree.insert_nodes_from_str(&node_str, record_file.clone(), None, &crate::PRESERVED_KEYS)
+        .map_err(|de_error| Error::CannotParse((record_file.clone(), Box::new(de_error))))?;
+    Ok(Some((existing_tree, node_str)))
+}
+
+// Generate the direct node of directory `dir_path` at `base_node_path`, recorded in `record_file`.
+//
+// If `existing` is given, the existing node of this directory is merged: its properties are kept,
+// its children come first in their original order, and files that are not recorded are appended.
 fn generate_direct_node(tree: &Tree,
                         base_node_path: NodePath,
                         dir_path: PathBuf,
-                        config: &GenerateConfig) -> Result<(Rc<RefCell<Node>>, Vec<(NodePath, PathBuf)>)> {
+                        record_file: &PathBuf,
+                        existing: Option<(&Tree, &DirectNode)>,
+                        ignorer: &Ignorer,
+                        config: &GenerateConfig) -> Result<(Rc<RefCell<Node>>, ChildDirs)> {
     let name = base_node_path.name().unwrap_or(tree.config().project_name);
-    let record_file = dir_path.join(&config.file_name);
     let node_properties = NodeProperties {
         name,
         parent: base_node_path.parent(),
-        record_file: record_file.clone()
+        record_file: record_file.clone(),
+        span: None
     };
 
     let mut dir_children_paths = vec![];
     let mut children = vec![];
 
+    let mut entries = vec![];
     for child in fs::read_dir(&dir_path)
-        .or_else(|io_error| Err(Error::CannotRead((dir_path.clone(), io_error))))? {
+        .map_err(|io_error| Error::CannotRead((dir_path.clone(), io_error)))? {
         let child = child.unwrap();
-        let child_name = child.file_name().to_str().unwrap().to_owned();
-        let child_path = base_node_path.push(child_name.clone());
         let metadata = child.metadata()
-            .or_else(|io_error| Err(Error::CannotRead((dir_path.clone(), io_error))))?;
-        if metadata.is_file() {
+            .map_err(|io_error| Error::CannotRead((dir_path.clone(), io_error)))?;
+        entries.push((child.file_name().to_str().unwrap().to_owned(), child.path(), metadata.is_file()));
+    }
+    // `read_dir` has no guaranteed order
+    entries.sort();
+
+    let mut properties = IndexMap::new();
+
+    if let Some((existing_tree, existing_node)) = existing {
+        properties = existing_properties(existing_node);
+        for existing_child_path in &existing_node.children {
+            let existing_child = existing_tree.get_node(existing_child_path).unwrap();
+            let existing_child = existing_child.borrow();
+            let child_name = existing_child.node_properties().name;
+            let child_path = base_node_path.push(child_name.clone());
+            let entry = entries.iter()
+                .position(|(entry_name, _, _)| entry_name == &child_name)
+                .map(|index| entries.remove(index));
+            if let Some((_, entry_path, is_file)) = entry {
+                match &*existing_child {
+                    Node::Link(link_node) => {
+                        let child_link_node = LinkNode {
+                            target: link_node.target.clone(),
+                            node_properties: NodeProperties {
+                                name: child_name,
+                                parent: Some(base_node_path.clone()),
+                                record_file: record_file.clone(),
+                                span: None
+                            }
+                        };
+                        if !is_file {
+                            let target_file = record_file.parent().unwrap().join(&link_node.target);
+                            dir_children_paths.push((child_path.clone(), entry_path, target_file));
+                        }
+                        tree.insert_node(child_path.clone(), Rc::new(RefCell::new(Node::Link(child_link_node))));
+                    },
+                    Node::Direct(direct_node) => {
+                        if is_file {
+                            let child_properties = existing_properties(direct_node);
+                            let child_direct_node = DirectNode {
+                                children: vec![],
+                                node_properties: NodeProperties {
+                                    name: child_name,
+                                    parent: Some(base_node_path.clone()),
+                                    record_file: record_file.clone(),
+                                    span: None
+                                },
+                                properties: child_properties,
+                                link_target: None
+                            };
+                            tree.insert_node(child_path.clone(), Rc::new(RefCell::new(Node::Direct(child_direct_node))));
+                        } else {
+                            // A directory recorded inline stays inline
+                            let (_, mut sub_dirs) = generate_direct_node(tree,
+                                                                         child_path.clone(),
+                                                                         entry_path,
+                                                                         record_file,
+                                                                         Some((existing_tree, direct_node)),
+                                                                         ignorer,
+                                                                         config)?;
+                            dir_children_paths.append(&mut sub_dirs);
+                        }
+                    }
+                }
+                children.push((child_path, !is_file));
+            } else if !config.prune {
+                let is_dir = match &*existing_child {
+                    Node::Direct(direct_node) => !direct_node.children.is_empty(),
+                    Node::Link(_) => true,
+                };
+                mark_missing(tree, child_path.clone(), &existing_child, existing_tree, record_file);
+                children.push((child_path, is_dir));
+            }
+        }
+    }
+
+    for (child_name, entry_path, is_file) in entries {
+        // The record file itself is not a new file to be documented
+        if &entry_path == record_file {
+            continue;
+        }
+        if ignorer.is_ignored(&entry_path, !is_file).map_err(Error::IgnoreFailed)? {
+            continue;
+        }
+        let child_path = base_node_path.push(child_name.clone());
+        if is_file {
             let child_direct_node = DirectNode {
                 children: vec![],
                 node_properties: NodeProperties {
                     name: child_name,
                     parent: Some(base_node_path.clone()),
-                    record_file: record_file.clone()
+                    record_file: record_file.clone(),
+                    span: None
                 },
-                properties: HashMap::new()
+                properties: IndexMap::new(),
+                link_target: None
             };
             let child_node = Rc::new(RefCell::new(Node::Direct(child_direct_node)));
             tree.insert_node(child_path.clone(), child_node);
         } else {
-            let target = PathBuf::from(child.file_name()).join(config.file_name.clone());
+            let target = PathBuf::from(&child_name).join(config.file_name.clone());
+            let target_file = entry_path.join(config.file_name.clone());
             let child_link_node = LinkNode {
                 target,
                 node_properties: NodeProperties {
-                    name: child.file_name().to_str().unwrap().to_owned(),
+                    name: child_name,
                     parent: Some(base_node_path.clone()),
-                    record_file: record_file.clone()
+                    record_file: record_file.clone(),
+                    span: None
                 }
             };
             let child_node = Rc::new(RefCell::new(Node::Link(child_link_node)));
             tree.insert_node(child_path.clone(), child_node);
-            dir_children_paths.push((child_path.clone(), child.path()));
+            dir_children_paths.push((child_path.clone(), entry_path, target_file));
         }
-        children.push(child_path);
+        children.push((child_path, !is_file));
+    }
+
+    match config.order {
+        ChildrenOrder::AsAuthored => {},
+        ChildrenOrder::Alphabetical => children.sort_by_key(|(child_path, _)| child_path.name()),
+        ChildrenOrder::DirectoriesFirst => children.sort_by_key(|(child_path, is_dir)| (!is_dir, child_path.name())),
     }
+    let children = children.into_iter().map(|(child_path, _)| child_path).collect();
 
     let direct_node = DirectNode {
         children,
         node_properties,
-        properties: HashMap::new()
+        properties,
+        link_target: None
     };
 
     let node = Rc::new(RefCell::new(Node::Direct(direct_node)));
@@ -166,15 +398,69 @@ fn generate_direct_node(tree: &Tree,
     Ok((node, dir_children_paths))
 }
 
+// The customized properties of `existing_node`, without the missing mark
+fn existing_properties(existing_node: &DirectNode) -> IndexMap<String, PropertyValue> {
+    let mut properties = existing_node.properties.clone();
+    properties.shift_remove(STATUS);
+    properties
+}
+
+// Copy `existing_node` and its inline descendants to `tree` at `node_path`, marking them as missing.
+// A link node is converted to a direct node, since it has no properties to hold the mark.
+fn mark_missing(tree: &Tree,
+                node_path: NodePath,
+                existing_node: &Node,
+                existing_tree: &Tree,
+                record_file: &PathBuf) {
+    let (mut properties, children) = if let Some(direct_node) = existing_node.get_direct() {
+        let children = direct_node.children.iter().map(|existing_child_path| {
+            let existing_child = existing_tree.get_node(existing_child_path).unwrap();
+            let child_path = node_path.push(existing_child.borrow().node_properties().name);
+            mark_missing(tree, child_path.clone(), &existing_child.borrow(), existing_tree, record_file);
+            child_path
+        }).collect();
+        (existing_properties(direct_node), children)
+    } else {
+        (IndexMap::new(), vec![])
+    };
+    properties.insert(STATUS.to_string(), PropertyValue::String(MISSING.to_string()));
+    let direct_node = DirectNode {
+        children,
+        node_properties: NodeProperties {
+            name: existing_node.node_properties().name,
+            parent: node_path.parent(),
+            record_file: record_file.clone(),
+            span: None
+        },
+        properties,
+        link_target: None
+    };
+    tree.insert_node(node_path, Rc::new(RefCell::new(Node::Direct(direct_node))));
+}
+
 #[derive(Debug)]
 pub enum Error {
     CannotRead((PathBuf, io::Error)),
     CannotWrite((PathBuf, io::Error)),
+    CannotParse((PathBuf, Box<de::Error>)),
     CurrentDirectoryNotAvailable(io::Error),
     PathNotDir(PathBuf),
+    IgnoreFailed(ignorer::Error),
+    UnknownOrder(String),
+    UnknownFormat(String),
+    /// The file name and the format it's expected to be in
+    FormatMismatch((String, String)),
+    CannotSerialize((PathBuf, Box<dyn error::Error>)),
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self {
+            Error::CannotParse((_, de_error)) => Some(&**de_error),
+            _ => None
+        }
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
@@ -182,8 +468,14 @@ impl fmt::Display for Error {
         let message = match &self {
             CannotRead((path, io_error)) => format!("Cannot open {}: {}", path.to_str().unwrap(), io_error),
             CannotWrite((path, io_error)) => format!("Cannot write to {}: {}", path.to_str().unwrap(), io_error),
+            CannotParse((path, de_error)) => format!("Cannot update {}: {}", path.to_str().unwrap(), de_error),
             CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
             PathNotDir(path) => format!("{} is not a directory", path.to_str().unwrap()),
+            IgnoreFailed(ignore_error) => format!("{}", ignore_error),
+            UnknownOrder(order) => format!("Unknown order {}.", order),
+            UnknownFormat(format) => format!("Unknown format {}.", format),
+            FormatMismatch((file_name, format)) => format!("File name {} is not of format {}.", file_name, format),
+            CannotSerialize((path, error)) => format!("Cannot serialize {}: {}", path.to_str().unwrap(), error),
         };
         write!(f, "{}", message)
     }
@@ -0,0 +1,141 @@
+use super::CommandlineOption;
+use super::query::node_value;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+use std::io::{self, Write};
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct GetOptions {
+    /// Key of the property, or one of "name", "path", "children", "parent" and "record_file". A nested value can be addressed by a dotted path like `owner.team`
+    #[structopt(long, short)]
+    key: String,
+
+    /// Node path for the node. "/" by default
+    #[structopt(long, short)]
+    path: Option<String>,
+
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root JSON file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+}
+
+struct GetConfig {
+    key: String,
+    path: String,
+    project_name: String,
+    file: PathBuf,
+}
+
+impl GetConfig {
+    fn from_opt(opt: GetOptions) -> Result<Self, Error> {
+        let key = opt.key;
+
+        let path = if let Some(path) = opt.path {
+            if path.starts_with('/') {
+                path
+            } else {
+                return Err(Error::NodePathMustBeAbsolute(path));
+            }
+        } else {
+            "/".to_string()
+        };
+
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        Ok(GetConfig {
+            key,
+            path,
+            project_name,
+            file
+        })
+    }
+}
+
+impl CommandlineOption for GetOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = GetConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+        let node_path = NodePath::from(&config.path, &tree)?;
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        write_value(&tree, &node_path, &config.key, &mut io::stdout())
+    }
+}
+
+/// Write the value of `key` of the resolved node at `node_path`. Children are written one per line
+pub fn write_value(tree: &Tree, node_path: &NodePath, key: &str, output: &mut dyn Write) -> Result<(), Box<dyn error::Error>> {
+    match (key, node_value(tree, node_path, key)?) {
+        ("children", Some(PropertyValue::Array(children))) => {
+            for child in children {
+                writeln!(output, "{}", child)?;
+            }
+        },
+        (_, Some(value)) => writeln!(output, "{}", value)?,
+        ("parent", None) => return Err(Box::new(Error::NoParent)),
+        (_, None) => return Err(Box::new(Error::PropertyInexist(key.to_string()))),
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NodePathMustBeAbsolute(String),
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    PropertyInexist(String),
+    NoParent,
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path),
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            PropertyInexist(key) => format!("The node does not have a property with key {}.", key),
+            NoParent => String::from("This node is root, who has no parent."),
+        };
+        write!(f, "{}", message)
+    }
+}
@@ -3,23 +3,77 @@ use std::fmt;
 use std::str::Chars;
 use std::iter::Peekable;
 
-pub fn to_args(input: &String) -> Result<Vec<String>, Error> {
+/// Where the output of a command goes, other than stdout
+pub enum Redirection {
+    /// `> file`
+    Overwrite(String),
+    /// `>> file`
+    Append(String),
+    /// `| shell command`
+    Pipe(String),
+}
+
+pub struct Command {
+    pub args: Vec<String>,
+    pub redirection: Option<Redirection>,
+}
+
+/// Split `input` into arguments. Unquoted `>`, `>>` and `|` are operators for redirection.
+///
+/// Everything after `|` is a shell command, which is kept as it is.
+pub fn to_command(input: &str) -> Result<Command, Error> {
     let mut chars = input.chars().peekable();
     let mut args = vec!["(wispha)".to_string()];
-    loop {
-        if let Some(next_char) = chars.peek() {
-            if next_char.is_whitespace() {
+    let mut redirection = None;
+    while let Some(next_char) = chars.peek() {
+        match *next_char {
+            '>' | '|' if redirection.is_some() => {
+                return Err(Error::MultipleRedirections);
+            },
+            '>' => {
                 chars.next();
-                continue;
-            } else {
+                let is_append = chars.peek() == Some(&'>');
+                let operator = if is_append {
+                    chars.next();
+                    ">>"
+                } else {
+                    ">"
+                };
+                while chars.peek().map(|next_char| next_char.is_whitespace()).unwrap_or(false) {
+                    chars.next();
+                }
+                match chars.peek() {
+                    None | Some('>') | Some('|') => return Err(Error::LackTarget(operator.to_string())),
+                    _ => {}
+                }
+                let file = to_arg(&mut chars)?;
+                redirection = Some(if is_append {
+                    Redirection::Append(file)
+                } else {
+                    Redirection::Overwrite(file)
+                });
+            },
+            '|' => {
+                chars.next();
+                let shell_command = chars.by_ref().collect::<String>().trim().to_string();
+                if shell_command.is_empty() {
+                    return Err(Error::LackTarget(String::from("|")));
+                }
+                redirection = Some(Redirection::Pipe(shell_command));
+            },
+            _ if next_char.is_whitespace() => {
+                chars.next();
+            },
+            _ => {
                 let arg = to_arg(&mut chars)?;
                 args.push(arg);
             }
-        } else {
-            break;
         }
     }
-    Ok(args)
+    Ok(Command {
+        args,
+        redirection
+    })
 }
 
 fn to_arg(chars: &mut Peekable<Chars>) -> Result<String, Error> {
@@ -28,7 +82,7 @@ fn to_arg(chars: &mut Peekable<Chars>) -> Result<String, Error> {
     let mut in_quote = false;
     loop {
         if let Some(next_char) = chars.peek() {
-            let next_char = next_char.clone();
+            let next_char = *next_char;
             match next_char {
                 '"' => {
                     in_quote = !in_quote;
@@ -42,6 +96,8 @@ fn to_arg(chars: &mut Peekable<Chars>) -> Result<String, Error> {
                             't' => res.push('\t'),
                             '\\' => res.push('\\'),
                             '"' => res.push('"'),
+                            '>' => res.push('>'),
+                            '|' => res.push('|'),
                             _ => {
                                 let illegal_escape_char = format!("\\{}", next_char);
                                 return Err(Error::IllegalEscapeChar(illegal_escape_char));
@@ -51,15 +107,16 @@ fn to_arg(chars: &mut Peekable<Chars>) -> Result<String, Error> {
                         return Err(Error::IllegalEscapeChar(String::from("\\")));
                     }
                 }
+                '>' | '|' if !in_quote => break,
                 _ if next_char.is_whitespace() => {
                     if in_quote {
-                        res.push(chars.next().unwrap().clone());
+                        res.push(chars.next().unwrap());
                     } else {
                         break;
                     }
                 },
                 _ => {
-                    res.push(chars.next().unwrap().clone());
+                    res.push(chars.next().unwrap());
                 }
             }
         } else {
@@ -77,6 +134,9 @@ fn to_arg(chars: &mut Peekable<Chars>) -> Result<String, Error> {
 pub enum Error {
     IllegalEscapeChar(String),
     UnbalancedQuote,
+    /// A redirection operator is not followed by a file or a shell command
+    LackTarget(String),
+    MultipleRedirections,
 }
 
 impl error::Error for Error { }
@@ -86,8 +146,61 @@ impl fmt::Display for Error {
         use Error::*;
         let message = match &self {
             IllegalEscapeChar(escape_char) => format!("Illegal escape char {}.", escape_char),
-            UnbalancedQuote => String::from("The quotation mark is unbalanced.")
+            UnbalancedQuote => String::from("The quotation mark is unbalanced."),
+            LackTarget(operator) => format!("Operator {} lacks its target.", operator),
+            MultipleRedirections => String::from("Output can only be redirected once."),
         };
         write!(f, "{}", message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<&str> {
+        command.args.iter().skip(1).map(|arg| arg.as_str()).collect()
+    }
+
+    #[test]
+    fn empty_command() {
+        for input in &["", "   \t"] {
+            let command = to_command(input).unwrap();
+            assert_eq!(command.args, vec!["(wispha)"]);
+            assert!(command.redirection.is_none());
+        }
+    }
+
+    #[test]
+    fn redirections() {
+        let command = to_command("layout -d 2 > out.txt").unwrap();
+        assert_eq!(args(&command), vec!["layout", "-d", "2"]);
+        assert!(matches!(&command.redirection, Some(Redirection::Overwrite(file)) if file == "out.txt"));
+
+        let command = to_command("layout>>out.txt").unwrap();
+        assert_eq!(args(&command), vec!["layout"]);
+        assert!(matches!(&command.redirection, Some(Redirection::Append(file)) if file == "out.txt"));
+
+        let command = to_command("layout | grep \"a b\" | wc -l").unwrap();
+        assert_eq!(args(&command), vec!["layout"]);
+        assert!(matches!(&command.redirection, Some(Redirection::Pipe(shell_command)) if shell_command == "grep \"a b\" | wc -l"));
+    }
+
+    #[test]
+    fn quoted_operators() {
+        let command = to_command(r#"set -v "a > b | c" \>\| > "my file""#).unwrap();
+        assert_eq!(args(&command), vec!["set", "-v", "a > b | c", ">|"]);
+        assert!(matches!(&command.redirection, Some(Redirection::Overwrite(file)) if file == "my file"));
+    }
+
+    #[test]
+    fn malformed_commands() {
+        assert!(matches!(to_command("set -v \"a b"), Err(Error::UnbalancedQuote)));
+        assert!(matches!(to_command("layout > \"out"), Err(Error::UnbalancedQuote)));
+        assert!(matches!(to_command("set -v a\\q"), Err(Error::IllegalEscapeChar(_))));
+        assert!(matches!(to_command("layout >"), Err(Error::LackTarget(operator)) if operator == ">"));
+        assert!(matches!(to_command("layout >> | less"), Err(Error::LackTarget(operator)) if operator == ">>"));
+        assert!(matches!(to_command("layout |  "), Err(Error::LackTarget(operator)) if operator == "|"));
+        assert!(matches!(to_command("layout > a > b"), Err(Error::MultipleRedirections)));
+    }
+}
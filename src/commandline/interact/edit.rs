@@ -0,0 +1,194 @@
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use structopt::StructOpt;
+
+use std::error;
+use std::fmt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SetOptions {
+    /// Node path for the node, absolute or relative to the current node. The current node by default
+    #[structopt(long, short)]
+    path: Option<String>,
+
+    /// Key of the property. A nested value can be addressed by a dotted path like `owner.team`
+    #[structopt(long, short)]
+    key: String,
+
+    /// Parse value as JSON, e.g. `true`, `2019` or `["io", "net"]`. Otherwise value is a string
+    #[structopt(long, short)]
+    json: bool,
+
+    /// Value of the property
+    value: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct UnsetOptions {
+    /// Node path for the node, absolute or relative to the current node. The current node by default
+    #[structopt(long, short)]
+    path: Option<String>,
+
+    /// Key of the property. A nested value can be addressed by a dotted path like `owner.team`
+    #[structopt(long, short)]
+    key: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct AddOptions {
+    /// Node path for the new node, absolute or relative to the current node
+    #[structopt(long, short)]
+    path: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct RmOptions {
+    /// Node path for the node to be removed with its descendants, absolute or relative to the current node
+    #[structopt(long, short)]
+    path: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct MvOptions {
+    /// Node path for the node to be moved, absolute or relative to the current node
+    #[structopt(long, short)]
+    path: String,
+
+    /// Node path for the new parent, absolute or relative to the current node. The node stays under its parent if not given
+    #[structopt(long, short)]
+    to: Option<String>,
+
+    /// New name for the node. The name is not changed if not given
+    #[structopt(long, short)]
+    name: Option<String>,
+}
+
+impl SetOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath) -> Result<(), Box<dyn error::Error>> {
+        let top_key = self.key.split('.').next().unwrap();
+        if crate::PRESERVED_KEYS.contains(&self.key.as_str()) || crate::PRESERVED_KEYS.contains(&top_key) {
+            return Err(Box::new(Error::PreservedKey(self.key)));
+        }
+        let value = if self.json {
+            serde_json::from_str(&self.value)
+                .map_err(|json_error| Error::InvalidJson((self.value.clone(), json_error)))?
+        } else {
+            PropertyValue::String(self.value)
+        };
+        let node_path = optional_path(&self.path, current);
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        tree.set_property(&node_path, &self.key, value)?;
+        Ok(())
+    }
+}
+
+impl UnsetOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath) -> Result<(), Box<dyn error::Error>> {
+        let node_path = optional_path(&self.path, current);
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        if tree.remove_property(&node_path, &self.key)?.is_none() {
+            return Err(Box::new(Error::PropertyInexist(self.key)));
+        }
+        Ok(())
+    }
+}
+
+impl AddOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath) -> Result<(), Box<dyn error::Error>> {
+        let node_path = current.join(&self.path);
+        let (parent, name) = if let (Some(parent), Some(name)) = (node_path.parent(), node_path.name()) {
+            (parent, name)
+        } else {
+            return Err(Box::new(Error::RootNotAllowed));
+        };
+        tree.resolve_node(&parent, &FsResolver, &crate::PRESERVED_KEYS)?;
+        tree.add_child(&parent, &name)?;
+        Ok(())
+    }
+}
+
+impl RmOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath) -> Result<(), Box<dyn error::Error>> {
+        let node_path = current.join(&self.path);
+        if node_path.parent().is_none() {
+            return Err(Box::new(Error::RootNotAllowed));
+        }
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        tree.remove_subtree(&node_path)?;
+        Ok(())
+    }
+}
+
+impl MvOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath) -> Result<(), Box<dyn error::Error>> {
+        if self.to.is_none() && self.name.is_none() {
+            return Err(Box::new(Error::NoDestination));
+        }
+        let node_path = current.join(&self.path);
+        let old_name = if let Some(old_name) = node_path.name() {
+            old_name
+        } else {
+            return Err(Box::new(Error::RootNotAllowed));
+        };
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        let to_parent = if let Some(to) = &self.to {
+            let to_parent = current.join(to);
+            tree.resolve_node(&to_parent, &FsResolver, &crate::PRESERVED_KEYS)?;
+            Some(to_parent)
+        } else {
+            None
+        };
+        let renamed_path = if let Some(name) = &self.name {
+            tree.rename_node(&node_path, name)?
+        } else {
+            node_path
+        };
+        if let Some(to_parent) = to_parent {
+            if let Err(error) = tree.move_node(&renamed_path, &to_parent) {
+                // Undo renaming, so that a failed `mv` changes nothing
+                tree.rename_node(&renamed_path, &old_name)?;
+                return Err(Box::new(error));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The node path of `path` relative to `current`, or `current` if `path` is not given
+fn optional_path(path: &Option<String>, current: &NodePath) -> NodePath {
+    if let Some(path) = path {
+        current.join(path)
+    } else {
+        current.clone()
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    PreservedKey(String),
+    InvalidJson((String, serde_json::Error)),
+    PropertyInexist(String),
+    RootNotAllowed,
+    NoDestination,
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            PreservedKey(key) => format!("Key {} is preserved.", key),
+            InvalidJson((value, json_error)) => format!("Invalid JSON value {}: {}", value, json_error),
+            PropertyInexist(key) => format!("The node does not have a property with key {}.", key),
+            RootNotAllowed => String::from("Root node can't be added, removed or moved."),
+            NoDestination => String::from("Either a new parent or a new name should be given."),
+        };
+        write!(f, "{}", message)
+    }
+}
@@ -0,0 +1,218 @@
+use crate::commandline::query::VIRTUAL_KEYS;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+use std::borrow::Cow;
+
+static SUBCOMMANDS: [&str; 16] = [
+    "layout", "get", "locate", "cd", "pwd", "ls", "set", "unset", "add", "rm", "mv", "save", "alias", "refresh", "quit", "help"
+];
+
+// Flags whose value is a node path
+static PATH_FLAGS: [&str; 4] = ["--path", "-p", "--to", "-t"];
+
+// Flags whose value is a key, or keys separated by `,`
+static KEY_FLAGS: [&str; 3] = ["--key", "--keys", "-k"];
+
+// The property shown as a hint for a node path
+static DESCRIPTION: &str = "description";
+
+fn flags(subcommand: &str) -> &'static [&'static str] {
+    match subcommand {
+        "layout" => &["--layout", "--path", "--keys", "--hide-key", "--depth"],
+        "get" => &["--key", "--path"],
+        "ls" => &["--long"],
+        "set" => &["--path", "--key", "--json"],
+        "unset" => &["--path", "--key"],
+        "add" | "rm" => &["--path"],
+        "mv" => &["--path", "--to", "--name"],
+        _ => &[]
+    }
+}
+
+/// What the word under the cursor is
+enum Slot {
+    Subcommand,
+    Flag(String),
+    NodePath,
+    /// A key of the node. Keys that `get` can display besides properties are included if the flag is set
+    Key((NodePath, bool)),
+    Nothing,
+}
+
+/// Completion and hints for the interactive mode
+pub struct InteractHelper {
+    tree: Tree,
+    pub current: NodePath,
+}
+
+impl InteractHelper {
+    pub fn new(tree: Tree, current: NodePath) -> InteractHelper {
+        InteractHelper {
+            tree,
+            current
+        }
+    }
+
+    // The start position and the kind of the word before `pos`
+    fn slot(&self, line: &str, pos: usize) -> (usize, Slot) {
+        let line = &line[..pos];
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (word, finished_words) = if line.ends_with(char::is_whitespace) || words.is_empty() {
+            ("", &words[..])
+        } else {
+            (words[words.len() - 1], &words[..words.len() - 1])
+        };
+        let start = pos - word.len();
+
+        let subcommand = if let Some(subcommand) = finished_words.first() {
+            *subcommand
+        } else {
+            return (start, Slot::Subcommand);
+        };
+        let previous = finished_words.last().unwrap();
+
+        if word.starts_with('-') {
+            (start, Slot::Flag(subcommand.to_string()))
+        } else if PATH_FLAGS.contains(previous) {
+            (start, Slot::NodePath)
+        } else if KEY_FLAGS.contains(previous) {
+            let path_index = finished_words.iter().position(|word| *word == "--path" || *word == "-p");
+            let node_path = if let Some(path) = path_index.and_then(|index| finished_words.get(index + 1)) {
+                self.current.join(path)
+            } else {
+                self.current.clone()
+            };
+            // `layout --keys` takes keys separated by `,`
+            let start = start + word.rfind(',').map(|index| index + 1).unwrap_or(0);
+            (start, Slot::Key((node_path, subcommand == "get")))
+        } else if (subcommand == "cd" || subcommand == "ls" || subcommand == "locate")
+            && (!previous.starts_with('-') || previous == &"--long" || previous == &"-l") {
+            (start, Slot::NodePath)
+        } else {
+            (start, Slot::Nothing)
+        }
+    }
+
+    fn candidates(&self, slot: &Slot, word: &str) -> Vec<String> {
+        let mut candidates = match slot {
+            Slot::Subcommand => SUBCOMMANDS.iter().map(|subcommand| subcommand.to_string()).collect(),
+            Slot::Flag(subcommand) => flags(subcommand).iter().map(|flag| flag.to_string()).collect(),
+            Slot::NodePath => self.node_path_candidates(word),
+            Slot::Key((node_path, with_virtual_keys)) => {
+                let mut keys = self.keys(node_path);
+                if *with_virtual_keys {
+                    keys.extend(VIRTUAL_KEYS.iter().map(|key| key.to_string()));
+                }
+                keys
+            },
+            Slot::Nothing => vec![],
+        };
+        candidates.retain(|candidate| candidate.starts_with(word));
+        candidates
+    }
+
+    // Children of the node that `word` is in, resolving it if it's a link node
+    fn node_path_candidates(&self, word: &str) -> Vec<String> {
+        let dir = &word[..word.rfind('/').map(|index| index + 1).unwrap_or(0)];
+        let base = self.current.join(dir);
+        if self.tree.resolve_node(&base, &FsResolver, &crate::PRESERVED_KEYS).is_err() {
+            return vec![];
+        }
+        let node = self.tree.get_node(&base).unwrap();
+        let node = node.borrow();
+        node.get_direct().unwrap().children.iter().filter_map(|child_path| {
+            let child = self.tree.get_node(child_path)?;
+            let child = child.borrow();
+            // Directories are completed with a trailing `/`, so that their children can be completed next
+            let suffix = match &*child {
+                Node::Direct(direct_node) if direct_node.children.is_empty() => "",
+                _ => "/",
+            };
+            Some(format!("{}{}{}", dir, child.node_properties().name, suffix))
+        }).collect()
+    }
+
+    // Keys of the properties of the node at `node_path`, with nested keys joined by `.`
+    fn keys(&self, node_path: &NodePath) -> Vec<String> {
+        if self.tree.resolve_node(node_path, &FsResolver, &crate::PRESERVED_KEYS).is_err() {
+            return vec![];
+        }
+        let node = self.tree.get_node(node_path).unwrap();
+        let node = node.borrow();
+        let mut keys = vec![];
+        for (key, value) in &node.get_direct().unwrap().properties {
+            push_keys(key.clone(), value, &mut keys);
+        }
+        keys
+    }
+
+    // The description of the node at `word`, if the node is loaded
+    fn description(&self, word: &str) -> Option<String> {
+        let node = self.tree.get_node(&self.current.join(word))?;
+        let node = node.borrow();
+        node.get_direct()?.properties.get(DESCRIPTION).map(|description| description.to_string())
+    }
+}
+
+fn push_keys(key: String, value: &PropertyValue, keys: &mut Vec<String>) {
+    if let PropertyValue::Object(map) = value {
+        for (sub_key, sub_value) in map {
+            push_keys(format!("{}.{}", key, sub_key), sub_value, keys);
+        }
+    }
+    keys.push(key);
+}
+
+impl Completer for InteractHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let (start, slot) = self.slot(line, pos);
+        Ok((start, self.candidates(&slot, &line[start..pos])))
+    }
+}
+
+impl Hinter for InteractHelper {
+    type Hint = String;
+
+    /// Hint the rest of the only candidate. For a node path, hint the node's description
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+        let (start, slot) = self.slot(line, pos);
+        let word = &line[start..pos];
+        if word.is_empty() {
+            return None;
+        }
+        let candidates = self.candidates(&slot, word);
+        if let [candidate] = candidates.as_slice() {
+            if candidate.len() > word.len() {
+                return Some(candidate[word.len()..].to_string());
+            }
+        }
+        if let Slot::NodePath = slot {
+            self.description(word).map(|description| format!("  {}", description))
+        } else {
+            None
+        }
+    }
+}
+
+impl Highlighter for InteractHelper {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        // dim
+        Cow::Owned(format!("\x1b[2m{}\x1b[0m", hint))
+    }
+}
+
+impl Validator for InteractHelper { }
+
+impl Helper for InteractHelper { }
@@ -5,8 +5,8 @@ use crate::layouter::Layout;
 use libwispha::core::*;
 use structopt::StructOpt;
 
-use std::fmt;
 use std::error;
+use std::io::Write;
 
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
@@ -15,11 +15,11 @@ pub struct LayoutOptions {
     #[structopt(long, short)]
     layout: Option<String>,
 
-    /// Node path for the node to be displayed at top level. "/" by default
+    /// Node path for the node to be displayed at top level, absolute or relative to the current node. The current node by default
     #[structopt(long, short)]
     path: Option<String>,
 
-    /// List of keys to be displayed. Empty list by default
+    /// List of keys to be displayed. A nested value can be addressed by a dotted path like `owner.team`. Empty list by default
     #[structopt(long, short, use_delimiter = true)]
     keys: Option<Vec<String>>,
 
@@ -34,83 +34,55 @@ pub struct LayoutOptions {
 
 struct LayoutConfig {
     layout: String,
-    path: String,
+    path: Option<String>,
     keys: Vec<String>,
     hide_key: bool,
     depth: usize,
 }
 
 impl LayoutConfig {
-    fn from_opt(layout_opt: LayoutOptions) -> Result<Self, Error> {
+    fn from_opt(layout_opt: LayoutOptions) -> Self {
         let layout = if let Some(layout) = layout_opt.layout {
             layout
         } else {
             line::LineLayout::info().name.clone()
         };
 
-        let path = if let Some(path) = layout_opt.path {
-            if path.starts_with("/") {
-                path
-            } else {
-                return Err(Error::NodePathMustBeAbsolute(path));
-            }
-        } else {
-            "/".to_string()
-        };
+        let path = layout_opt.path;
 
-        let keys = if let Some(keys) = layout_opt.keys {
-            keys
-        } else {
-            vec![]
-        };
+        let keys = layout_opt.keys.unwrap_or_default();
 
-        let hide_key = layout_opt.hide_key.clone();
+        let hide_key = layout_opt.hide_key;
 
-        let depth = if let Some(depth) = layout_opt.depth {
-            depth
-        } else {
-            3
-        };
+        let depth = layout_opt.depth.unwrap_or(3);
 
-        Ok(LayoutConfig {
+        LayoutConfig {
             layout,
             path,
             keys,
             hide_key,
             depth
-        })
+        }
     }
 }
 
 impl LayoutOptions {
-    pub fn run(self, tree: &Tree, manager: &LayoutManager) -> Result<(), Box<dyn error::Error>> {
-        let config = LayoutConfig::from_opt(self)?;
+    pub fn run(self, tree: &Tree, current: &NodePath, manager: &LayoutManager, output: &mut dyn Write) -> Result<(), Box<dyn error::Error>> {
+        let config = LayoutConfig::from_opt(self);
 
-        let node_path = NodePath::from(&config.path, &tree)?;
-        let layout_str = manager.layout(&config.layout,
-                                        &tree,
+        let node_path = if let Some(path) = &config.path {
+            current.join(path)
+        } else {
+            current.clone()
+        };
+        let layout_str = manager.layout(&line::LineLayout::info().format,
+                                        &config.layout,
+                                        tree,
                                         &node_path,
                                         config.depth,
                                         &config.keys,
                                         config.hide_key)?;
-        println!("{}", layout_str);
+        writeln!(output, "{}", layout_str)?;
         Ok(())
     }
 }
-
-#[derive(Debug)]
-pub enum Error {
-    NodePathMustBeAbsolute(String),
-}
-
-impl error::Error for Error { }
-
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        use Error::*;
-        let message = match &self {
-            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path),
-        };
-        write!(f, "{}", message)
-    }
-}
\ No newline at end of file
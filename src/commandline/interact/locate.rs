@@ -0,0 +1,27 @@
+use crate::commandline::locate::location;
+
+use libwispha::core::{Tree, NodePath};
+use structopt::StructOpt;
+
+use std::error;
+use std::io::Write;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct LocateOptions {
+    /// Node path for the node, absolute or relative to the current node. The current node by default
+    path: Option<String>,
+}
+
+impl LocateOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath, output: &mut dyn Write) -> Result<(), Box<dyn error::Error>> {
+        let node_path = if let Some(path) = &self.path {
+            current.join(path)
+        } else {
+            current.clone()
+        };
+        tree.resolve_node(&node_path, &libwispha::resolver::FsResolver, &crate::PRESERVED_KEYS)?;
+        writeln!(output, "{}", location(tree, &node_path)?)?;
+        Ok(())
+    }
+}
@@ -1,19 +1,31 @@
 mod commandline_parser;
 mod layout;
 mod get;
+mod locate;
+mod edit;
+mod navigate;
+mod helper;
 
-use super::CommandlineOption;
+use super::{CommandlineOption, error_snippet};
+use commandline_parser::Redirection;
 use crate::layout_templates::LayoutManager;
 
 use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use indexmap::IndexMap;
 use structopt::StructOpt;
-use rustyline;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
 
 use std::error;
 use std::path::PathBuf;
 use std::env;
 use std::fmt;
 use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::fs::{File, OpenOptions};
+use std::process::{self, Stdio};
 
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
@@ -25,11 +37,65 @@ pub struct InteractOptions {
     /// File path for the project's root JSON file. `LOOKME.json` By default
     #[structopt(long, short)]
     file: Option<PathBuf>,
+
+    /// File to keep command history. `.wispha/history` in the project directory by default
+    #[structopt(long)]
+    history: Option<PathBuf>,
+
+    /// Neither load nor save command history
+    #[structopt(long, conflicts_with = "history")]
+    no_history: bool,
+
+    /// Script whose commands are run before the prompt appears.
+    /// `.wispha/interactrc` in the project directory by default, if it exists
+    #[structopt(long)]
+    init: Option<PathBuf>,
+
+    /// Don't run the init script
+    #[structopt(long, conflicts_with = "init")]
+    no_init: bool,
+
+    /// Run the commands in the file instead of prompting. If stdin is not a terminal, commands are read from it
+    #[structopt(long, short)]
+    script: Option<PathBuf>,
+
+    /// When running commands from a script or stdin, continue after a command fails
+    #[structopt(long)]
+    keep_going: bool,
 }
 
 struct InteractConfig {
     project_name: String,
     file: PathBuf,
+    history: Option<PathBuf>,
+    init: Option<PathBuf>,
+    batch: Option<Batch>,
+    keep_going: bool,
+}
+
+/// Where commands are read from when not prompting
+enum Batch {
+    Script(PathBuf),
+    Stdin,
+}
+
+/// The result of running a script
+struct ScriptResult {
+    failures: usize,
+    quit: bool,
+}
+
+static HISTORY_FILE: &str = "history";
+static INIT_FILE: &str = "interactrc";
+// Lines starting with it in the init script are comments
+static COMMENT_PREFIX: &str = "#";
+
+/// The state of an interactive session
+struct Session {
+    current: NodePath,
+    /// Whether the tree is edited and not saved yet
+    is_dirty: bool,
+    aliases: IndexMap<String, String>,
 }
 
 #[derive(StructOpt)]
@@ -41,11 +107,60 @@ enum Subcommand {
     /// Get a key for a node
     Get(get::GetOptions),
 
-    /// Refresh nodes cache
+    /// Print `file:line:col` where a node is recorded
+    Locate(locate::LocateOptions),
+
+    /// Change the current node
+    Cd(navigate::CdOptions),
+
+    /// Print the current node
+    Pwd,
+
+    /// List the children of a node
+    Ls(navigate::LsOptions),
+
+    /// Set a property for a node
+    Set(edit::SetOptions),
+
+    /// Remove a property from a node
+    Unset(edit::UnsetOptions),
+
+    /// Add a new node
+    Add(edit::AddOptions),
+
+    /// Remove a node and its descendants
+    Rm(edit::RmOptions),
+
+    /// Move or rename a node
+    Mv(edit::MvOptions),
+
+    /// Write edits to the files the edited nodes are recorded in
+    Save,
+
+    /// Define an alias, e.g. `alias ll "ls --long"`. List all aliases if no name is given
+    Alias {
+        /// Name of the alias
+        name: Option<String>,
+
+        /// The command that the alias stands for
+        command: Option<String>,
+    },
+
+    /// Refresh nodes cache. Unsaved edits are discarded
     Refresh,
     Quit,
 }
 
+/// What the interactive loop should do after a command
+enum Outcome {
+    Continue,
+    /// The tree is edited and not saved yet
+    Edited,
+    /// The tree is the same as the files
+    Synced,
+    Quit,
+}
+
 impl InteractConfig {
     fn from_opt(opt: InteractOptions) -> Result<InteractConfig, Error> {
         let project_name = if let Some(project_name) = opt.project_name {
@@ -59,32 +174,232 @@ impl InteractConfig {
                 file
             } else {
                 env::current_dir()
-                    .map_err(|io_error| Error::CurrentDirectoryNotAvailable(io_error))?
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
                     .join(file)
             }
         } else {
             env::current_dir()
-                .map_err(|io_error| Error::CurrentDirectoryNotAvailable(io_error))?
+                .map_err(Error::CurrentDirectoryNotAvailable)?
                 .join("LOOKME.json")
         };
 
+        let project_dir = file.parent().unwrap().join(crate::WISPHA_DIR);
+
+        let history = if opt.no_history {
+            None
+        } else if let Some(history) = opt.history {
+            Some(history)
+        } else {
+            Some(project_dir.join(HISTORY_FILE))
+        };
+
+        let init = if opt.no_init {
+            None
+        } else if let Some(init) = opt.init {
+            if !init.is_file() {
+                return Err(Error::PathNotExist(init));
+            }
+            Some(init)
+        } else {
+            Some(project_dir.join(INIT_FILE)).filter(|init| init.is_file())
+        };
+
+        let batch = if let Some(script) = opt.script {
+            Some(Batch::Script(script))
+        } else if !io::stdin().is_terminal() {
+            Some(Batch::Stdin)
+        } else {
+            None
+        };
+
         Ok(InteractConfig {
             project_name,
-            file
+            file,
+            history,
+            init,
+            batch,
+            keep_going: opt.keep_going
         })
     }
 
-    fn run_helper(&self, line: &String, tree: &Tree, manager: &LayoutManager) -> Result<bool, Box<dyn error::Error>> {
+    // Run the commands in `script` named `name` line by line. Empty lines and comments are skipped.
+    // Failed commands are reported with their line numbers. Unless `keep_going`, stop at the first failure.
+    fn run_script(&self,
+                  name: &str,
+                  script: &str,
+                  keep_going: bool,
+                  tree: &Tree,
+                  session: &mut Session,
+                  manager: &LayoutManager) -> ScriptResult {
+        let mut result = ScriptResult {
+            failures: 0,
+            quit: false
+        };
+        for (index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+            match self.run_helper(line, tree, session, manager) {
+                Ok(Outcome::Quit) => {
+                    result.quit = true;
+                    break;
+                },
+                Ok(outcome) => session.update(outcome, tree),
+                Err(error) => {
+                    eprintln!("{}:{}: {}", name, index + 1, error);
+                    if let Some(snippet) = error_snippet(&*error) {
+                        eprintln!("{}", snippet);
+                    }
+                    result.failures += 1;
+                    if !keep_going {
+                        break;
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    fn run_helper(&self,
+                  line: &str,
+                  tree: &Tree,
+                  session: &mut Session,
+                  manager: &LayoutManager) -> Result<Outcome, Box<dyn error::Error>> {
+        let mut command = commandline_parser::to_command(line)?;
+        if let Some(alias) = command.args.get(1).and_then(|name| session.aliases.get(name)) {
+            let mut expanded_command = commandline_parser::to_command(alias)?;
+            expanded_command.args.extend(command.args.drain(2..));
+            if command.redirection.is_some() {
+                if expanded_command.redirection.is_some() {
+                    return Err(Box::new(commandline_parser::Error::MultipleRedirections));
+                }
+                expanded_command.redirection = command.redirection;
+            }
+            command = expanded_command;
+        }
+        let interact_opt = Subcommand::from_iter_safe(command.args)?;
+
+        let mut shell_process = None;
+        let mut output: Box<dyn Write> = match &command.redirection {
+            None => Box::new(io::stdout()),
+            Some(Redirection::Overwrite(file)) => Box::new(
+                File::create(file).map_err(|io_error| Error::CannotWrite((PathBuf::from(file), io_error)))?
+            ),
+            Some(Redirection::Append(file)) => Box::new(
+                OpenOptions::new().create(true).append(true).open(file)
+                    .map_err(|io_error| Error::CannotWrite((PathBuf::from(file), io_error)))?
+            ),
+            Some(Redirection::Pipe(shell_command)) => {
+                let (shell, option) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+                let mut process = process::Command::new(shell)
+                    .arg(option)
+                    .arg(shell_command)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .map_err(|io_error| Error::CannotRunShell((shell_command.clone(), io_error)))?;
+                let stdin = process.stdin.take().unwrap();
+                shell_process = Some(process);
+                Box::new(stdin)
+            }
+        };
+
+        let outcome = self.run_subcommand(interact_opt, tree, session, manager, &mut output)
+            .and_then(|outcome| {
+                output.flush()?;
+                Ok(outcome)
+            });
+        // Close the pipe, so that the shell command sees the end of its input
+        drop(output);
+        let shell_status = if let (Some(mut process), Some(Redirection::Pipe(shell_command))) = (shell_process, &command.redirection) {
+            let status = process.wait()
+                .map_err(|io_error| Error::CannotRunShell((shell_command.clone(), io_error)))?;
+            Some((shell_command, status))
+        } else {
+            None
+        };
+        let outcome = match outcome {
+            // The shell command may exit without reading all the output, e.g. `| head -1`
+            Err(error) if shell_process_closed(&*error) => Outcome::Continue,
+            outcome => outcome?
+        };
+        if let Some((shell_command, status)) = shell_status {
+            if !status.success() {
+                return Err(Box::new(Error::ShellFailed((shell_command.clone(), status))));
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn run_subcommand(&self,
+                      interact_opt: Subcommand,
+                      tree: &Tree,
+                      session: &mut Session,
+                      manager: &LayoutManager,
+                      output: &mut dyn Write) -> Result<Outcome, Box<dyn error::Error>> {
         use Subcommand::*;
 
-        let args = commandline_parser::to_args(&line)?;
-        let interact_opt = Subcommand::from_iter_safe(args)?;
+        let current = &session.current;
         match interact_opt {
             Layout(layout_options) => {
-                layout_options.run(tree, manager)?;
+                layout_options.run(tree, current, manager, output)?;
             },
             Get(get_options) => {
-                get_options.run(tree)?;
+                get_options.run(tree, current, output)?;
+            },
+            Locate(locate_options) => {
+                locate_options.run(tree, current, output)?;
+            },
+            Cd(cd_options) => {
+                session.current = cd_options.run(tree, current)?;
+            },
+            Pwd => {
+                writeln!(output, "{}", current)?;
+            },
+            Ls(ls_options) => {
+                ls_options.run(tree, current, output)?;
+            },
+            Set(set_options) => {
+                set_options.run(tree, current)?;
+                return Ok(Outcome::Edited);
+            },
+            Unset(unset_options) => {
+                unset_options.run(tree, current)?;
+                return Ok(Outcome::Edited);
+            },
+            Add(add_options) => {
+                add_options.run(tree, current)?;
+                return Ok(Outcome::Edited);
+            },
+            Rm(rm_options) => {
+                rm_options.run(tree, current)?;
+                return Ok(Outcome::Edited);
+            },
+            Mv(mv_options) => {
+                mv_options.run(tree, current)?;
+                return Ok(Outcome::Edited);
+            },
+            Save => {
+                for file in tree.save()? {
+                    writeln!(output, "Saved {}", file.to_str().unwrap())?;
+                }
+                return Ok(Outcome::Synced);
+            },
+            Alias { name, command } => {
+                match (name, command) {
+                    (Some(name), Some(command)) => {
+                        session.aliases.insert(name, command);
+                    },
+                    (Some(name), None) => {
+                        let command = session.aliases.get(&name).ok_or(Error::AliasInexist(name.clone()))?;
+                        writeln!(output, "alias {} \"{}\"", name, command)?;
+                    },
+                    _ => {
+                        for (name, command) in &session.aliases {
+                            writeln!(output, "alias {} \"{}\"", name, command)?;
+                        }
+                    }
+                }
             },
             Refresh => {
                 let node_str = fs::read_to_string(&self.file)
@@ -93,11 +408,12 @@ impl InteractConfig {
                 // Clear after read to string successfully
                 tree.clear();
 
-                tree.insert_nodes_from_str(&node_str, self.file.clone(), None, &*crate::PRESERVED_KEYS)?;
+                tree.insert_nodes_from_str(&node_str, self.file.clone(), None, &crate::PRESERVED_KEYS)?;
+                return Ok(Outcome::Synced);
             },
-            Quit => return Ok(true),
+            Quit => return Ok(Outcome::Quit),
         }
-        Ok(false)
+        Ok(Outcome::Continue)
     }
 }
 
@@ -112,20 +428,86 @@ impl CommandlineOption for InteractOptions {
         let tree = Tree::new(&tree_config);
         let node_str = fs::read_to_string(&config.file)
             .or(Err(Error::PathNotExist(config.file.clone())))?;
-        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &*crate::PRESERVED_KEYS)?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
 
         let layout_manager = LayoutManager::new();
 
-        let mut rl = rustyline::Editor::<()>::new();
+        let mut session = Session {
+            current: NodePath::new(&tree),
+            is_dirty: false,
+            aliases: IndexMap::new()
+        };
+
+        if let Some(init) = &config.init {
+            let script = fs::read_to_string(init)
+                .map_err(|io_error| Error::CannotRead((init.clone(), io_error)))?;
+            let result = config.run_script(init.to_str().unwrap(), &script, true, &tree, &mut session, &layout_manager);
+            if result.quit {
+                return Ok(());
+            }
+        }
+
+        if let Some(batch) = &config.batch {
+            let (name, script) = match batch {
+                Batch::Script(script_file) => {
+                    let script = fs::read_to_string(script_file)
+                        .map_err(|io_error| Error::CannotRead((script_file.clone(), io_error)))?;
+                    (script_file.to_str().unwrap().to_string(), script)
+                },
+                Batch::Stdin => {
+                    let mut script = String::new();
+                    io::stdin().read_to_string(&mut script)
+                        .map_err(|io_error| Error::CannotRead((PathBuf::from("stdin"), io_error)))?;
+                    ("stdin".to_string(), script)
+                }
+            };
+            let result = config.run_script(&name, &script, config.keep_going, &tree, &mut session, &layout_manager);
+            if session.is_dirty {
+                eprintln!("Unsaved edits are discarded.");
+            }
+            return if result.failures > 0 {
+                Err(Box::new(Error::CommandsFailed(result.failures)))
+            } else {
+                Ok(())
+            };
+        }
+
+        let mut rl = Editor::<helper::InteractHelper, DefaultHistory>::new()?;
+        rl.set_helper(Some(helper::InteractHelper::new(tree.clone(), session.current.clone())));
+        if let Some(history) = &config.history {
+            // There is no history file before the first session
+            if history.is_file() {
+                rl.load_history(history)?;
+            }
+        }
         let mut line;
 
         loop {
-            line = rl.readline("(wispha) ");
+            if let Some(helper) = rl.helper_mut() {
+                helper.current = session.current.clone();
+            }
+            // `*` marks unsaved edits
+            let prompt = format!("(wispha{} {}) ", if session.is_dirty { "*" } else { "" }, session.current);
+            line = rl.readline(&prompt);
             match &line {
                 Ok(line) => {
-                    match config.run_helper(&line, &tree, &layout_manager) {
-                        Ok(will_quit) => if will_quit { break } else { continue },
-                        Err(error) => eprintln!("{}", error)
+                    if !line.trim().is_empty() {
+                        rl.add_history_entry(line.as_str())?;
+                    }
+                    match config.run_helper(line, &tree, &mut session, &layout_manager) {
+                        Ok(Outcome::Quit) => if !session.is_dirty || confirm_quit(&mut rl) { break },
+                        Ok(outcome) => session.update(outcome, &tree),
+                        Err(error) => {
+                            eprintln!("{}", error);
+                            if let Some(snippet) = error_snippet(&*error) {
+                                eprintln!("{}", snippet);
+                            }
+                        }
+                    }
+                },
+                Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
+                    if !session.is_dirty || confirm_quit(&mut rl) {
+                        break;
                     }
                 },
                 Err(error) => {
@@ -135,14 +517,77 @@ impl CommandlineOption for InteractOptions {
             }
         }
 
+        if let Some(history) = &config.history {
+            if let Some(history_dir) = history.parent() {
+                fs::create_dir_all(history_dir)
+                    .map_err(|io_error| Error::CannotWrite((history.clone(), io_error)))?;
+            }
+            rl.save_history(history)?;
+        }
+
         Ok(())
     }
 }
 
+impl Session {
+    // Update the state after a command which does not quit
+    fn update(&mut self, outcome: Outcome, tree: &Tree) {
+        match outcome {
+            Outcome::Edited => {
+                self.is_dirty = true;
+                self.current = existing_ancestor(tree, &self.current);
+            },
+            Outcome::Synced => {
+                self.is_dirty = false;
+                self.current = existing_ancestor(tree, &self.current);
+            },
+            Outcome::Continue | Outcome::Quit => {}
+        }
+    }
+}
+
+// The nearest node of `node_path` and its ancestors that still exists, after the tree is edited or refreshed
+fn existing_ancestor(tree: &Tree, node_path: &NodePath) -> NodePath {
+    let mut node_path = node_path.clone();
+    while tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS).is_err() {
+        if let Some(parent) = node_path.parent() {
+            node_path = parent;
+        } else {
+            break;
+        }
+    }
+    node_path
+}
+
+// Whether `error` is caused by a shell command that stopped reading its input
+fn shell_process_closed(error: &(dyn error::Error + 'static)) -> bool {
+    error.downcast_ref::<io::Error>()
+         .map(|io_error| io_error.kind() == io::ErrorKind::BrokenPipe)
+         .unwrap_or(false)
+}
+
+// Ask whether to quit with unsaved edits. Quit only if user answers yes
+fn confirm_quit(rl: &mut Editor<helper::InteractHelper, DefaultHistory>) -> bool {
+    match rl.readline("There are unsaved edits. Quit anyway? [y/N] ") {
+        Ok(answer) => {
+            let answer = answer.trim().to_lowercase();
+            answer == "y" || answer == "yes"
+        },
+        Err(_) => true
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     CurrentDirectoryNotAvailable(std::io::Error),
     PathNotExist(PathBuf),
+    CannotRead((PathBuf, std::io::Error)),
+    CannotWrite((PathBuf, std::io::Error)),
+    AliasInexist(String),
+    CommandsFailed(usize),
+    CannotRunShell((String, std::io::Error)),
+    /// A shell command piped to exits with failure
+    ShellFailed((String, process::ExitStatus)),
 }
 
 impl error::Error for Error { }
@@ -153,7 +598,51 @@ impl fmt::Display for Error {
         let message = match &self {
             CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
             PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            CannotRead((path, io_error)) => format!("Cannot open {}: {}", path.to_str().unwrap(), io_error),
+            CannotWrite((path, io_error)) => format!("Cannot write to {}: {}", path.to_str().unwrap(), io_error),
+            AliasInexist(name) => format!("There is no alias named {}.", name),
+            CommandsFailed(count) => format!("{} command(s) failed.", count),
+            CannotRunShell((shell_command, io_error)) => format!("Cannot run shell command {}: {}", shell_command, io_error),
+            ShellFailed((shell_command, status)) => format!("Shell command {} failed: {}", shell_command, status),
         };
         write!(f, "{}", message)
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_shell_command() {
+        let tree = Tree::new(&TreeConfig {
+            project_name: "Project".to_string()
+        });
+        tree.insert_nodes_from_str(r#"{"children": [{"name": "a.rs"}]}"#,
+                                   PathBuf::from("/project/LOOKME.json"),
+                                   None,
+                                   &crate::PRESERVED_KEYS).unwrap();
+        let manager = LayoutManager::new();
+        let mut session = Session {
+            current: NodePath::new(&tree),
+            is_dirty: false,
+            aliases: IndexMap::new()
+        };
+        let config = InteractConfig {
+            project_name: "Project".to_string(),
+            file: PathBuf::from("/project/LOOKME.json"),
+            history: None,
+            init: None,
+            batch: None,
+            keep_going: true
+        };
+
+        assert!(config.run_helper("pwd | cat > /dev/null", &tree, &mut session, &manager).is_ok());
+        let error = config.run_helper("pwd | false", &tree, &mut session, &manager).err().unwrap();
+        assert!(matches!(error.downcast_ref::<Error>(), Some(Error::ShellFailed((shell_command, _))) if shell_command == "false"));
+
+        let script = "pwd | false\nls | true\nls | exit 3\n";
+        let result = config.run_script("script", script, true, &tree, &mut session, &manager);
+        assert_eq!(result.failures, 2);
+    }
+}
@@ -0,0 +1,84 @@
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use structopt::StructOpt;
+
+use std::error;
+use std::io::Write;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CdOptions {
+    /// Node path for the new current node, absolute or relative to the current node. Root by default
+    path: Option<String>,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct LsOptions {
+    /// Display the properties of each child
+    #[structopt(long, short)]
+    long: bool,
+
+    /// Node path for the node whose children are listed, absolute or relative to the current node. The current node by default
+    path: Option<String>,
+}
+
+impl CdOptions {
+    /// Return the new current node
+    pub fn run(self, tree: &Tree, current: &NodePath) -> Result<NodePath, Box<dyn error::Error>> {
+        let node_path = if let Some(path) = &self.path {
+            current.join(path)
+        } else {
+            NodePath::new(tree)
+        };
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        Ok(node_path)
+    }
+}
+
+impl LsOptions {
+    pub fn run(self, tree: &Tree, current: &NodePath, output: &mut dyn Write) -> Result<(), Box<dyn error::Error>> {
+        let node_path = if let Some(path) = &self.path {
+            current.join(path)
+        } else {
+            current.clone()
+        };
+        let depth = if self.long { 1 } else { 0 };
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        tree.resolve_in_depth(&node_path, depth, &FsResolver, &crate::PRESERVED_KEYS)?;
+
+        // After resolving, there must be a direct node at `node_path`
+        let node = tree.get_node(&node_path).unwrap();
+        let node = node.borrow();
+        let names_and_appendices = node.get_direct().unwrap().children.iter().map(|child_path| {
+            let child = tree.get_node(child_path).unwrap();
+            let child = child.borrow();
+            // Directories are marked with a trailing `/`
+            let name = match &*child {
+                Node::Direct(direct_node) if direct_node.children.is_empty() => direct_node.node_properties.name.clone(),
+                _ => format!("{}/", child.node_properties().name),
+            };
+            let appendix = if let (true, Some(direct_node)) = (self.long, child.get_direct()) {
+                direct_node.properties.iter()
+                           .map(|(key, value)| format!("{}: {}", key, value))
+                           .collect::<Vec<_>>()
+                           .join("\t\t")
+            } else {
+                String::new()
+            };
+            (name, appendix)
+        }).collect::<Vec<_>>();
+
+        let max_len = names_and_appendices.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+        for (name, appendix) in names_and_appendices {
+            if appendix.is_empty() {
+                writeln!(output, "{}", name)?;
+            } else {
+                let remain = max_len + 4 - name.chars().count();
+                writeln!(output, "{}{}{}", name, " ".repeat(remain), appendix)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::layout_templates::line;
+use crate::layout_templates::{line, markdown, html};
 use crate::layouter::Layout;
 use super::CommandlineOption;
 
@@ -14,7 +14,12 @@ use std::fs;
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub struct LayoutOptions {
-    /// Name for layout template. For now, it's "plain", "line" or "triangle". "line" by default
+    /// Output format. For now, it's "text", "markdown" or "html". "text" by default
+    #[structopt(long)]
+    format: Option<String>,
+
+    /// Name for layout template of the format. For now, it's "plain", "line" or "triangle" for text,
+    /// "list" or "table" for markdown, and "details" for html. "line", "list" and "details" by default respectively
     #[structopt(long, short)]
     layout: Option<String>,
 
@@ -26,7 +31,8 @@ pub struct LayoutOptions {
     #[structopt(long, short)]
     path: Option<String>,
 
-    /// List of keys to be displayed. Empty list by default
+    /// List of keys to be displayed. A nested value can be addressed by a dotted path like `owner.team`.
+    /// Empty list by default for text, and `description` for markdown and html
     #[structopt(long, short, use_delimiter = true)]
     keys: Option<Vec<String>>,
 
@@ -44,6 +50,7 @@ pub struct LayoutOptions {
 }
 
 struct LayoutConfig {
+    format: String,
     layout: String,
     project_name: String,
     path: String,
@@ -55,10 +62,16 @@ struct LayoutConfig {
 
 impl LayoutConfig {
     fn from_opt(opt: LayoutOptions) -> Result<Self, Error> {
+        let format = opt.format.unwrap_or_else(|| line::LineLayout::info().format);
+
         let layout = if let Some(layout) = opt.layout {
             layout
         } else {
-            line::LineLayout::info().name.clone()
+            match format.as_str() {
+                "markdown" => markdown::MarkdownListLayout::info().name,
+                "html" => html::HtmlLayout::info().name,
+                _ => line::LineLayout::info().name
+            }
         };
 
         let project_name = if let Some(project_name) = opt.project_name {
@@ -79,33 +92,32 @@ impl LayoutConfig {
 
         let keys = if let Some(keys) = opt.keys {
             keys
-        } else {
+        } else if format == line::LineLayout::info().format {
             vec![]
+        } else {
+            vec!["description".to_string()]
         };
 
-        let hide_key = opt.hide_key.clone();
+        let hide_key = opt.hide_key;
 
         let file = if let Some(file) = opt.file {
             if file.is_absolute() {
                 file
             } else {
                 env::current_dir()
-                    .map_err(|io_error| Error::CurrentDirectoryNotAvailable(io_error))?
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
                     .join(file)
             }
         } else {
             env::current_dir()
-                .map_err(|io_error| Error::CurrentDirectoryNotAvailable(io_error))?
+                .map_err(Error::CurrentDirectoryNotAvailable)?
                 .join("LOOKME.json")
         };
 
-        let depth = if let Some(depth) = opt.depth {
-            depth
-        } else {
-            3
-        };
+        let depth = opt.depth.unwrap_or(3);
 
         Ok(LayoutConfig {
+            format,
             layout,
             project_name,
             path,
@@ -128,12 +140,13 @@ impl CommandlineOption for LayoutOptions {
         let tree = Tree::new(&tree_config);
         let node_str = fs::read_to_string(&config.file)
             .or(Err(Error::PathNotExist(config.file.clone())))?;
-        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &*crate::PRESERVED_KEYS)?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
         let node_path = NodePath::from(&config.path, &tree)?;
 
         let layout_manager = crate::layout_templates::LayoutManager::new();
 
-        let layout_str = layout_manager.layout(&config.layout,
+        let layout_str = layout_manager.layout(&config.format,
+                                               &config.layout,
                                                &tree,
                                                &node_path,
                                                config.depth,
@@ -0,0 +1,118 @@
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct LocateOptions {
+    /// Node path for the node to be located
+    path: String,
+
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root JSON file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+}
+
+struct LocateConfig {
+    path: String,
+    project_name: String,
+    file: PathBuf,
+}
+
+impl LocateConfig {
+    fn from_opt(opt: LocateOptions) -> Result<Self, Error> {
+        let path = if opt.path.starts_with('/') {
+            opt.path
+        } else {
+            return Err(Error::NodePathMustBeAbsolute(opt.path));
+        };
+
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        Ok(LocateConfig {
+            path,
+            project_name,
+            file
+        })
+    }
+}
+
+impl CommandlineOption for LocateOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = LocateConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+        let node_path = NodePath::from(&config.path, &tree)?;
+        tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+        println!("{}", location(&tree, &node_path)?);
+        Ok(())
+    }
+}
+
+/// `file:line:col` of the resolved node at `node_path`
+pub fn location(tree: &Tree, node_path: &NodePath) -> Result<String, Error> {
+    let node = tree.get_node(node_path).ok_or_else(|| Error::NotRecorded(node_path.clone()))?;
+    let node_properties = node.borrow().node_properties();
+    let span = node_properties.span.ok_or_else(|| Error::NotRecorded(node_path.clone()))?;
+    Ok(format!("{}:{}:{}", node_properties.record_file.to_string_lossy(), span.start_line, span.start_column))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NodePathMustBeAbsolute(String),
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    NotRecorded(NodePath),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path),
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            NotRecorded(node_path) => format!("Node {} is not recorded in a file yet.", node_path),
+        };
+        write!(f, "{}", message)
+    }
+}
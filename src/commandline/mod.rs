@@ -1,3 +1,4 @@
+use libwispha::serde::de;
 use structopt::StructOpt;
 
 use std::error;
@@ -5,11 +6,31 @@ use std::error;
 mod layout;
 mod interact;
 mod generate;
+mod check;
+mod drift;
+mod get;
+mod query;
+mod explain;
+mod locate;
+mod convert;
+mod export;
 
 pub trait CommandlineOption {
     fn run(self) -> Result<(), Box<dyn error::Error>>;
 }
 
+/// The source snippet of the deserialization error, if `error` or one of its sources is
+pub fn error_snippet(error: &(dyn error::Error + 'static)) -> Option<String> {
+    let mut error = Some(error);
+    while let Some(current) = error {
+        if let Some(de_error) = current.downcast_ref::<de::Error>() {
+            return Some(de_error.location().snippet());
+        }
+        error = current.source();
+    }
+    None
+}
+
 #[derive(StructOpt)]
 pub enum Commandline {
     /// Display a project layout
@@ -19,7 +40,31 @@ pub enum Commandline {
     Generate(generate::GenerateOptions),
 
     /// Enter interact mode
-    Interact(interact::InteractOptions)
+    Interact(interact::InteractOptions),
+
+    /// Check the whole project tree and report all problems
+    Check(check::CheckOptions),
+
+    /// Report nodes missing in filesystem, and files not documented in the project tree
+    Drift(drift::DriftOptions),
+
+    /// Display a property of a node
+    Get(get::GetOptions),
+
+    /// Display properties of nodes as text, JSON or TSV
+    Query(query::QueryOptions),
+
+    /// Explain a file or directory with its node's properties and its ancestors' descriptions
+    Explain(explain::ExplainOptions),
+
+    /// Print `file:line:col` where a node is recorded
+    Locate(locate::LocateOptions),
+
+    /// Convert record files to another format, or restructure them into one file or files per directory
+    Convert(convert::ConvertOptions),
+
+    /// Export the project layout as a Graphviz DOT or Mermaid graph
+    Export(export::ExportOptions),
 }
 
 impl CommandlineOption for Commandline {
@@ -29,6 +74,14 @@ impl CommandlineOption for Commandline {
             Layout(layout_options) => layout_options.run(),
             Generate(generate_options) => generate_options.run(),
             Interact(interact_options) => interact_options.run(),
+            Check(check_options) => check_options.run(),
+            Drift(drift_options) => drift_options.run(),
+            Get(get_options) => get_options.run(),
+            Query(query_options) => query_options.run(),
+            Explain(explain_options) => explain_options.run(),
+            Locate(locate_options) => locate_options.run(),
+            Convert(convert_options) => convert_options.run(),
+            Export(export_options) => export_options.run(),
         }
     }
 }
@@ -0,0 +1,388 @@
+use super::CommandlineOption;
+
+use libwispha::core::*;
+use libwispha::resolver::FsResolver;
+use serde_json::json;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::env;
+use std::fmt;
+use std::error;
+use std::fs;
+
+/// Keys that can be queried besides properties
+pub static VIRTUAL_KEYS: [&str; 5] = ["name", "path", "children", "parent", "record_file"];
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct QueryOptions {
+    /// List of node paths for the nodes to be queried. "/" by default
+    #[structopt(long, short, use_delimiter = true)]
+    paths: Option<Vec<String>>,
+
+    /// List of keys to be displayed, including "name", "path", "children", "parent" and "record_file". A nested value can be addressed by a dotted path like `owner.team`. All properties by default
+    #[structopt(long, short, use_delimiter = true)]
+    keys: Option<Vec<String>>,
+
+    /// Depth of descendants to be queried as well. 0 by default
+    #[structopt(long, short)]
+    depth: Option<usize>,
+
+    /// Output format. For now, it's "text", "json" or "tsv". "text" by default
+    #[structopt(long)]
+    format: Option<String>,
+
+    /// Name for the project, used for the name of top directory. "." by default
+    #[structopt(long, short = "n")]
+    project_name: Option<String>,
+
+    /// File path for the project's root JSON file. `LOOKME.json` By default
+    #[structopt(long, short)]
+    file: Option<PathBuf>,
+}
+
+enum QueryFormat {
+    Text,
+    Json,
+    Tsv,
+}
+
+struct QueryConfig {
+    paths: Vec<String>,
+    keys: Vec<String>,
+    depth: usize,
+    format: QueryFormat,
+    project_name: String,
+    file: PathBuf,
+}
+
+/// Values of a queried node, in the order of keys. A value is `None` if the node does not have the key
+struct QueryEntry {
+    node_path: NodePath,
+    values: Vec<(String, Option<PropertyValue>)>,
+}
+
+impl QueryConfig {
+    fn from_opt(opt: QueryOptions) -> Result<Self, Error> {
+        let paths = if let Some(paths) = opt.paths {
+            if let Some(path) = paths.iter().find(|path| !path.starts_with('/')) {
+                return Err(Error::NodePathMustBeAbsolute(path.clone()));
+            }
+            paths
+        } else {
+            vec!["/".to_string()]
+        };
+
+        let keys = opt.keys.unwrap_or_default();
+
+        let depth = opt.depth.unwrap_or(0);
+
+        let format = if let Some(format) = opt.format {
+            match format.as_str() {
+                "text" => QueryFormat::Text,
+                "json" => QueryFormat::Json,
+                "tsv" => QueryFormat::Tsv,
+                _ => return Err(Error::UnknownFormat(format))
+            }
+        } else {
+            QueryFormat::Text
+        };
+
+        let project_name = if let Some(project_name) = opt.project_name {
+            project_name
+        } else {
+            ".".to_string()
+        };
+
+        let file = if let Some(file) = opt.file {
+            if file.is_absolute() {
+                file
+            } else {
+                env::current_dir()
+                    .map_err(Error::CurrentDirectoryNotAvailable)?
+                    .join(file)
+            }
+        } else {
+            env::current_dir()
+                .map_err(Error::CurrentDirectoryNotAvailable)?
+                .join("LOOKME.json")
+        };
+
+        Ok(QueryConfig {
+            paths,
+            keys,
+            depth,
+            format,
+            project_name,
+            file
+        })
+    }
+}
+
+impl CommandlineOption for QueryOptions {
+    fn run(self) -> Result<(), Box<dyn error::Error>> {
+        let config = QueryConfig::from_opt(self)?;
+
+        let tree_config = TreeConfig {
+            project_name: config.project_name.clone()
+        };
+
+        let tree = Tree::new(&tree_config);
+        let node_str = fs::read_to_string(&config.file)
+            .or(Err(Error::PathNotExist(config.file.clone())))?;
+        tree.insert_nodes_from_str(&node_str, config.file.clone(), None, &crate::PRESERVED_KEYS)?;
+
+        let mut node_paths = vec![];
+        for path in &config.paths {
+            let node_path = NodePath::from(path, &tree)?;
+            tree.resolve_node(&node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+            tree.resolve_in_depth(&node_path, config.depth, &FsResolver, &crate::PRESERVED_KEYS)?;
+            push_descendants(&tree, &node_path, config.depth, &mut node_paths);
+        }
+
+        let mut entries = vec![];
+        for node_path in node_paths {
+            let keys = if config.keys.is_empty() {
+                // After resolving in depth, there must be a direct node at `node_path`
+                let node = tree.get_node(&node_path).unwrap();
+                let node = node.borrow();
+                node.get_direct().unwrap().properties.keys().cloned().collect()
+            } else {
+                config.keys.clone()
+            };
+            let mut values = vec![];
+            for key in keys {
+                let value = node_value(&tree, &node_path, &key)?;
+                values.push((key, value));
+            }
+            entries.push(QueryEntry {
+                node_path,
+                values
+            });
+        }
+
+        let output = match config.format {
+            QueryFormat::Text => to_text(&entries),
+            QueryFormat::Json => to_json(&entries),
+            QueryFormat::Tsv => to_tsv(&entries, &config.keys),
+        };
+        println!("{}", output);
+        Ok(())
+    }
+}
+
+// Push `node_path` and its descendants within `depth` in pre-order
+fn push_descendants(tree: &Tree, node_path: &NodePath, depth: usize, node_paths: &mut Vec<NodePath>) {
+    node_paths.push(node_path.clone());
+    if depth == 0 {
+        return;
+    }
+    // After resolving in depth, there must be a direct node at `node_path`
+    let node = tree.get_node(node_path).unwrap();
+    let children = node.borrow().get_direct().unwrap().children.clone();
+    for child in &children {
+        push_descendants(tree, child, depth - 1, node_paths);
+    }
+}
+
+/// The value of property `key` of the node at `node_path`, or the value of a virtual key if the node has no such property.
+///
+/// The node should have been resolved. `None` is returned if neither exists, or the key is `parent` and the node is root.
+pub fn node_value(tree: &Tree, node_path: &NodePath, key: &str) -> Result<Option<PropertyValue>, Box<dyn error::Error>> {
+    let node = tree.get_node(node_path).ok_or_else(|| Error::NodeNotResolved(node_path.clone()))?;
+    let node = node.borrow();
+    let direct_node = node.get_direct().ok_or_else(|| Error::NodeNotResolved(node_path.clone()))?;
+    if let Some(property) = direct_node.get_property(key) {
+        return Ok(Some(property.clone()));
+    }
+    let value = match key {
+        "name" => Some(PropertyValue::String(direct_node.node_properties.name.clone())),
+        "path" => {
+            let path = tree.get_path_buf(node_path)?;
+            Some(PropertyValue::String(path.to_string_lossy().to_string()))
+        },
+        "children" => {
+            let children = direct_node.children.iter()
+                .map(|child| PropertyValue::String(child.to_string()))
+                .collect();
+            Some(PropertyValue::Array(children))
+        },
+        "parent" => direct_node.node_properties.parent.as_ref()
+            .map(|parent| PropertyValue::String(parent.to_string())),
+        "record_file" => {
+            let record_file = &direct_node.node_properties.record_file;
+            Some(PropertyValue::String(record_file.to_string_lossy().to_string()))
+        },
+        _ => None
+    };
+    Ok(value)
+}
+
+fn to_text(entries: &[QueryEntry]) -> String {
+    let mut lines = vec![];
+    for entry in entries {
+        lines.push(entry.node_path.to_string());
+        for (key, value) in &entry.values {
+            if let Some(value) = value {
+                lines.push(format!("    {}: {}", key, value));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn to_json(entries: &[QueryEntry]) -> String {
+    let entries = entries.iter().map(|entry| {
+        let properties = entry.values.iter()
+            .map(|(key, value)| (key.clone(), json!(value)))
+            .collect::<serde_json::Map<_, _>>();
+        json!({
+            "node_path": entry.node_path.to_string(),
+            "properties": properties
+        })
+    }).collect::<Vec<_>>();
+    serde_json::to_string_pretty(&entries).unwrap()
+}
+
+// If `keys` is empty, columns are all the keys of the entries in order of appearance
+fn to_tsv(entries: &[QueryEntry], keys: &[String]) -> String {
+    let mut columns = keys.to_vec();
+    if columns.is_empty() {
+        for entry in entries {
+            for (key, _) in &entry.values {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+    }
+
+    let mut lines = vec![];
+    let header = std::iter::once("node_path").chain(columns.iter().map(|key| key.as_str()));
+    lines.push(header.map(escape_tsv).collect::<Vec<_>>().join("\t"));
+    for entry in entries {
+        let mut fields = vec![escape_tsv(&entry.node_path.to_string())];
+        for column in &columns {
+            let value = entry.values.iter()
+                .find(|(key, _)| key == column)
+                .and_then(|(_, value)| value.as_ref())
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            fields.push(escape_tsv(&value));
+        }
+        lines.push(fields.join("\t"));
+    }
+    lines.join("\n")
+}
+
+// Tabs, newlines and backslashes in a field are escaped, so that each line is a row
+fn escape_tsv(field: &str) -> String {
+    field.replace('\\', "\\\\")
+         .replace('\t', "\\t")
+         .replace('\n', "\\n")
+         .replace('\r', "\\r")
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NodePathMustBeAbsolute(String),
+    CurrentDirectoryNotAvailable(std::io::Error),
+    PathNotExist(PathBuf),
+    UnknownFormat(String),
+    NodeNotResolved(NodePath),
+}
+
+impl error::Error for Error { }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use Error::*;
+        let message = match &self {
+            NodePathMustBeAbsolute(path) => format!("Node path must be absolute, but {} is not.", path),
+            CurrentDirectoryNotAvailable(io_error) => format!("Can not access current directory: {}", io_error),
+            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
+            UnknownFormat(format) => format!("Unknown format {}.", format),
+            NodeNotResolved(node_path) => format!("Node {} is not resolved.", node_path),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Tree {
+        let tree = Tree::new(&TreeConfig {
+            project_name: "Project".to_string()
+        });
+        let json_str = r#"{"description": "root", "children": [
+            {"name": "a", "description": "line 1\nline 2", "owner": {"team": "core"}},
+            {"name": "b", "tags": ["x\ty", "z"]}
+        ]}"#;
+        tree.insert_nodes_from_str(json_str, PathBuf::from("/project/LOOKME.json"), None, &crate::PRESERVED_KEYS).unwrap();
+        tree
+    }
+
+    fn entries(tree: &Tree, keys: &[&str]) -> Vec<QueryEntry> {
+        ["/a", "/b"].iter().map(|path| {
+            let node_path = NodePath::from(path, tree).unwrap();
+            let values = keys.iter()
+                .map(|key| (key.to_string(), node_value(tree, &node_path, key).unwrap()))
+                .collect();
+            QueryEntry {
+                node_path,
+                values
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn virtual_keys() {
+        let tree = tree();
+        let value = |path: &str, key: &str| node_value(&tree, &NodePath::from(path, &tree).unwrap(), key).unwrap();
+        assert_eq!(value("/a", "name"), Some(PropertyValue::String("a".to_string())));
+        assert_eq!(value("/a", "path"), Some(PropertyValue::String("/project/a".to_string())));
+        assert_eq!(value("/a", "parent"), Some(PropertyValue::String("/".to_string())));
+        assert_eq!(value("/", "parent"), None);
+        assert_eq!(value("/", "children"), Some(PropertyValue::Array(vec![
+            PropertyValue::String("/a".to_string()),
+            PropertyValue::String("/b".to_string())
+        ])));
+        assert_eq!(value("/b", "record_file"), Some(PropertyValue::String("/project/LOOKME.json".to_string())));
+        assert_eq!(value("/a", "owner.team"), Some(PropertyValue::String("core".to_string())));
+        assert_eq!(value("/b", "size"), None);
+    }
+
+    #[test]
+    fn tsv_output() {
+        let tree = tree();
+        let keys = vec!["description".to_string(), "tags".to_string()];
+        let entries = entries(&tree, &["description", "tags"]);
+        assert_eq!(to_tsv(&entries, &keys), "node_path\tdescription\ttags\n\
+                                              /a\tline 1\\nline 2\t\n\
+                                              /b\t\t[x\\ty, z]");
+    }
+
+    #[test]
+    fn tsv_columns_in_order_of_appearance() {
+        let tree = tree();
+        let mut entries = entries(&tree, &[]);
+        entries[0].values.push(("owner".to_string(), Some(PropertyValue::String("me".to_string()))));
+        entries[1].values.push(("name".to_string(), Some(PropertyValue::String("b".to_string()))));
+        entries[1].values.push(("owner".to_string(), None));
+        assert_eq!(to_tsv(&entries, &[]), "node_path\towner\tname\n/a\tme\t\n/b\t\tb");
+    }
+
+    #[test]
+    fn json_output() {
+        let tree = tree();
+        let entries = entries(&tree, &["owner", "size"]);
+        let output: serde_json::Value = serde_json::from_str(&to_json(&entries)).unwrap();
+        assert_eq!(output, json!([
+            {"node_path": "/a", "properties": {"owner": {"team": "core"}, "size": null}},
+            {"node_path": "/b", "properties": {"owner": null, "size": null}}
+        ]));
+    }
+}
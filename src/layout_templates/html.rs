@@ -0,0 +1,143 @@
+use libwispha::core::*;
+
+use crate::layouter::*;
+
+use std::error;
+
+/// A self-contained page, where directories are collapsible and nodes can be filtered by their names and properties
+pub struct HtmlLayout { }
+
+static STYLE: &str = r#"body { font-family: sans-serif; margin: 2em; }
+#filter { width: 24em; padding: 0.3em; margin-bottom: 1em; }
+ul.tree, ul.tree ul { list-style: none; padding-left: 1.2em; margin: 0; }
+ul.tree { padding-left: 0; }
+summary, .leaf { padding: 0.1em 0; }
+.leaf { padding-left: 1.1em; }
+.name { font-family: monospace; font-weight: bold; }
+.property { color: #555; margin-left: 1em; }
+.key { color: #888; }
+.hidden { display: none; }"#;
+
+// A node is shown if itself, one of its ancestors or one of its descendants matches the filter.
+// Directories are opened if one of their descendants matches
+static SCRIPT: &str = r#"const filter = document.getElementById("filter");
+function apply(node, text, ancestorMatched) {
+  const label = node.querySelector(":scope > details > summary, :scope > .leaf");
+  const matched = label.textContent.toLowerCase().includes(text);
+  let descendantMatched = false;
+  for (const child of node.querySelectorAll(":scope > details > ul > li")) {
+    if (apply(child, text, ancestorMatched || matched)) {
+      descendantMatched = true;
+    }
+  }
+  const details = node.querySelector(":scope > details");
+  if (details && text !== "") {
+    details.open = descendantMatched;
+  } else if (details) {
+    details.open = true;
+  }
+  node.classList.toggle("hidden", !(matched || ancestorMatched || descendantMatched));
+  return matched || descendantMatched;
+}
+filter.addEventListener("input", () => {
+  const text = filter.value.trim().toLowerCase();
+  for (const node of document.querySelectorAll("ul.tree > li")) {
+    apply(node, text, false);
+  }
+});"#;
+
+impl Layout for HtmlLayout {
+    fn info() -> LayoutInfo {
+        LayoutInfo {
+            name: "details".to_string(),
+            format: "html".to_string(),
+            version: "1.0".to_string()
+        }
+    }
+
+    fn layout(tree: &Tree,
+              node_path: &NodePath,
+              depth: usize,
+              keys: &[String],
+              hide_key: bool) -> Result<String, Box<dyn error::Error>> {
+        let entries = walk(tree, node_path, depth, keys)?;
+        let title = format!("Layout of {}", node_path);
+
+        let mut body = String::new();
+        // Depths of the directories whose `<details>` are not closed yet
+        let mut opened: Vec<usize> = vec![];
+        for entry in &entries {
+            while opened.last().map(|&depth| depth >= entry.depth).unwrap_or(false) {
+                opened.pop();
+                body += &close_directory(opened.len());
+            }
+
+            let indent = "  ".repeat(opened.len() * 2 + 1);
+            let label = label(entry, hide_key && keys.len() == 1);
+            if entry.has_children {
+                body += &format!("{}<li><details open><summary>{}</summary>\n{}  <ul>\n", indent, label, indent);
+                opened.push(entry.depth);
+            } else {
+                body += &format!("{}<li><div class=\"leaf\">{}</div></li>\n", indent, label);
+            }
+        }
+        while opened.pop().is_some() {
+            body += &close_directory(opened.len());
+        }
+
+        Ok(format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+{style}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<input id="filter" type="search" placeholder="Filter by name or property" autofocus>
+<ul class="tree">
+{body}</ul>
+<script>
+{script}
+</script>
+</body>
+</html>"#, title = escape(&title), style = STYLE, body = body, script = SCRIPT))
+    }
+}
+
+// Close the `<details>` of a directory, which is in `level` directories
+fn close_directory(level: usize) -> String {
+    let indent = "  ".repeat(level * 2 + 1);
+    format!("{}  </ul>\n{}</details></li>\n", indent, indent)
+}
+
+// The name and properties of a node
+fn label(entry: &LayoutEntry, hide_key: bool) -> String {
+    let mut label = format!("<span class=\"name\">{}</span>", escape(&entry.name));
+    for (key, value) in &entry.properties {
+        label += "<span class=\"property\">";
+        if !hide_key {
+            label += &format!("<span class=\"key\">{}:</span> ", escape(key));
+        }
+        label += &escape(value);
+        label += "</span>";
+    }
+    label
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\'' => escaped += "&#39;",
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
@@ -0,0 +1,120 @@
+use libwispha::core::*;
+
+use crate::layouter::*;
+
+use std::error;
+
+/// Nested bullet lists, one item for each node
+pub struct MarkdownListLayout { }
+
+/// A table with a column for node paths, and a column for each key
+pub struct MarkdownTableLayout { }
+
+impl Layout for MarkdownListLayout {
+    fn info() -> LayoutInfo {
+        LayoutInfo {
+            name: "list".to_string(),
+            format: "markdown".to_string(),
+            version: "1.0".to_string()
+        }
+    }
+
+    fn layout(tree: &Tree,
+              node_path: &NodePath,
+              depth: usize,
+              keys: &[String],
+              hide_key: bool) -> Result<String, Box<dyn error::Error>> {
+        let entries = walk(tree, node_path, depth, keys)?;
+        let lines = entries.into_iter().map(|entry| {
+            let mut line = "  ".repeat(entry.depth);
+            line += "- ";
+            line += &code(&entry.name);
+
+            let hide_key = hide_key && keys.len() == 1;
+            let appendix = entry.properties.iter().map(|(key, value)| {
+                if hide_key {
+                    escape(value)
+                } else {
+                    format!("{}: {}", escape(key), escape(value))
+                }
+            }).collect::<Vec<_>>().join("; ");
+            if !appendix.is_empty() {
+                line += " — ";
+                line += &appendix;
+            }
+            line
+        }).collect::<Vec<_>>();
+        Ok(lines.join("\n"))
+    }
+}
+
+impl Layout for MarkdownTableLayout {
+    fn info() -> LayoutInfo {
+        LayoutInfo {
+            name: "table".to_string(),
+            format: "markdown".to_string(),
+            version: "1.0".to_string()
+        }
+    }
+
+    // Keys are always shown as column headers, so `hide_key` is of no use
+    fn layout(tree: &Tree,
+              node_path: &NodePath,
+              depth: usize,
+              keys: &[String],
+              _hide_key: bool) -> Result<String, Box<dyn error::Error>> {
+        let entries = walk(tree, node_path, depth, keys)?;
+
+        let mut header = vec!["Path".to_string()];
+        header.extend(keys.iter().map(|key| escape(key).replace('|', "\\|")));
+        let mut rows = vec![header, vec!["---".to_string(); keys.len() + 1]];
+
+        for entry in entries {
+            let mut path = entry.node_path.to_string();
+            if entry.has_children && path != "/" {
+                path += "/";
+            }
+            // `|` ends a table cell even in inline code
+            let mut row = vec![code(&path).replace('|', "\\|")];
+            row.extend(keys.iter().map(|key| {
+                entry.properties.iter()
+                    .find(|(property_key, _)| property_key == key)
+                    .map(|(_, value)| escape(value).replace('|', "\\|"))
+                    .unwrap_or_default()
+            }));
+            rows.push(row);
+        }
+
+        Ok(rows.into_iter()
+            .map(|row| format!("| {} |", row.join(" | ")))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+// `text` as inline code, which is enclosed with more backticks if it contains any
+fn code(text: &str) -> String {
+    if text.contains('`') {
+        format!("`` {} ``", text)
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+// Escape characters which have meanings in Markdown, and line breaks which end a list item or a table row.
+// `|` only has a meaning in tables, so it's escaped by the table layout
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\n' => escaped.push(' '),
+            '\r' => { },
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
@@ -1,43 +1,27 @@
 pub mod plain;
 pub mod line;
 pub mod triangle;
+pub mod markdown;
+pub mod html;
 
 use libwispha::core::*;
 
 use crate::layouter::Layout;
 
-use std::fs;
 use std::error;
 use std::fmt;
-use std::path::PathBuf;
 use std::collections::HashMap;
 
-pub fn resolve_handler(link_node: &LinkNode) -> Result<(PathBuf, String), Box<dyn error::Error>> {
-    let path = if link_node.target.is_absolute() {
-        link_node.target.clone()
-    } else {
-        link_node.node_properties.record_file.parent().unwrap()
-                 .join(link_node.target.clone())
-    };
-    if path == link_node.node_properties.record_file {
-        Err(Box::new(Error::LoopTarget(path.clone())))
-    } else {
-        let node_str = fs::read_to_string(&path)
-            .or(Err(Box::new(Error::PathNotExist(path.clone()))))?;
-        Ok((path, node_str))
-    }
-}
+type LayoutFn = fn(tree: &Tree,
+                   node_path: &NodePath,
+                   depth: usize,
+                   keys: &[String],
+                   hide_key: bool) -> Result<String, Box<dyn error::Error>>;
 
 // see https://stackoverflow.com/questions/60312249/how-to-store-structs-not-instance-that-implement-a-common-trait/60312850#60312850
 pub struct LayoutManager {
-    templates: HashMap<
-        String,
-        fn(tree: &Tree,
-           node_path: &NodePath,
-           depth: usize,
-           keys: &Vec<String>,
-           hide_key: bool) -> Result<String, Box<dyn error::Error>>
-    >
+    // Templates by their formats and names
+    templates: HashMap<(String, String), LayoutFn>
 }
 
 impl LayoutManager {
@@ -46,34 +30,41 @@ impl LayoutManager {
         manager.register_template::<plain::PlainLayout>();
         manager.register_template::<line::LineLayout>();
         manager.register_template::<triangle::TriangleLayout>();
+        manager.register_template::<markdown::MarkdownListLayout>();
+        manager.register_template::<markdown::MarkdownTableLayout>();
+        manager.register_template::<html::HtmlLayout>();
         manager
     }
 
     fn register_template<T: Layout>(&mut self) {
-        let name = &T::info().name;
-        self.templates.insert(name.clone(), T::layout);
+        let info = T::info();
+        self.templates.insert((info.format, info.name), T::layout);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn layout(&self,
-                  template: &String,
+                  format: &str,
+                  template: &str,
                   tree: &Tree,
                   node_path: &NodePath,
                   depth: usize,
-                  keys: &Vec<String>,
+                  keys: &[String],
                   hide_key: bool) -> Result<String, Box<dyn error::Error>> {
-        if let Some(layout) = self.templates.get(template) {
+        if let Some(layout) = self.templates.get(&(format.to_string(), template.to_string())) {
             layout(tree, node_path, depth, keys, hide_key)
+        } else if self.templates.keys().any(|(template_format, _)| template_format == format) {
+            Err(Box::new(Error::LayoutNotFound((template.to_string(), format.to_string()))))
         } else {
-            Err(Box::new(Error::LayoutNotFound(template.clone())))
+            Err(Box::new(Error::FormatNotFound(format.to_string())))
         }
     }
 }
 
 #[derive(Debug)]
 enum Error {
-    PathNotExist(PathBuf),
-    LoopTarget(PathBuf),
-    LayoutNotFound(String)
+    /// Name of the layout, and the format it's looked up in
+    LayoutNotFound((String, String)),
+    FormatNotFound(String),
 }
 
 impl error::Error for Error { }
@@ -82,9 +73,8 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         use Error::*;
         let message = match &self {
-            PathNotExist(path) => format!("Can't open file at {}.", path.to_str().unwrap()),
-            LoopTarget(path) => format!("A node in file {} has target to the file itself.", path.to_str().unwrap()),
-            LayoutNotFound(name) => format!("Can't find a layout named {}.", name)
+            LayoutNotFound((name, format)) => format!("Can't find a layout named {} in format {}.", name, format),
+            FormatNotFound(format) => format!("Can't find any layout in format {}.", format),
         };
         write!(f, "{}", message)
     }
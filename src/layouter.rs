@@ -1,9 +1,14 @@
 use libwispha::core::*;
+use libwispha::resolver::FsResolver;
 
 use std::error;
+use std::path::Path;
 
 pub struct LayoutInfo {
     pub name: String,
+    /// Output format of the template, like "text", "markdown" or "html"
+    pub format: String,
+    #[allow(dead_code)]
     pub version: String
 }
 
@@ -19,6 +24,115 @@ pub trait Layout {
     fn layout(tree: &Tree,
               node_path: &NodePath,
               depth: usize,
-              keys: &Vec<String>,
+              keys: &[String],
               hide_key: bool) -> Result<String, Box<dyn error::Error>>;
-}
\ No newline at end of file
+}
+
+/// A node to be displayed in a layout
+pub struct LayoutEntry {
+    pub node_path: NodePath,
+    pub name: String,
+    /// The top node is at depth 0
+    pub depth: usize,
+    /// Whether the node is the last child of its parent
+    pub is_last: bool,
+    /// Whether the node has children that are displayed
+    pub has_children: bool,
+    /// Whether the node is recorded in another file than its parent, that is, it's the target of a link
+    pub linked: bool,
+    /// Displayed keys that the node has, with their values
+    pub properties: Vec<(String, String)>,
+}
+
+/// Resolve the tree relative to `node_path` in `depth`, and list the nodes to be displayed in pre-order,
+/// with the properties of `keys`
+pub fn walk(tree: &Tree,
+            node_path: &NodePath,
+            depth: usize,
+            keys: &[String]) -> Result<Vec<LayoutEntry>, Box<dyn error::Error>> {
+    tree.resolve_node(node_path, &FsResolver, &crate::PRESERVED_KEYS)?;
+    tree.resolve_in_depth(node_path, depth, &FsResolver, &crate::PRESERVED_KEYS)?;
+    let mut entries = vec![];
+    walk_helper(tree, node_path, None, false, 0, depth, keys, &mut entries);
+    Ok(entries)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk_helper(tree: &Tree,
+               node_path: &NodePath,
+               parent_record_file: Option<&Path>,
+               is_last: bool,
+               depth: usize,
+               max: usize,
+               keys: &[String],
+               entries: &mut Vec<LayoutEntry>) {
+    // Can safely unwrap because of the effect of `resolve_node`
+    let node = tree.get_node(node_path).unwrap();
+    let node = node.borrow();
+    let direct_node = node.get_direct().unwrap();
+
+    let record_file = direct_node.node_properties.record_file.as_path();
+
+    let properties = keys.iter().filter_map(|key| {
+        direct_node.get_property(key).map(|property| (key.clone(), property.to_string()))
+    }).collect();
+
+    entries.push(LayoutEntry {
+        node_path: node_path.clone(),
+        name: direct_node.node_properties.name.clone(),
+        depth,
+        is_last,
+        has_children: depth < max && !direct_node.children.is_empty(),
+        linked: parent_record_file.map(|parent_record_file| parent_record_file != record_file).unwrap_or(false),
+        properties
+    });
+
+    if depth < max {
+        for (index, child_path) in direct_node.children.iter().enumerate() {
+            let is_last = index + 1 == direct_node.children.len();
+            walk_helper(tree, child_path, Some(record_file), is_last, depth + 1, max, keys, entries);
+        }
+    }
+}
+
+/// The properties of `entry` displayed after its name in text layouts.
+///
+/// If `keys` only has one element, and `hide_key` is `true`, only the value is displayed
+pub fn text_appendix(entry: &LayoutEntry, keys: &[String], hide_key: bool) -> String {
+    let mut appendix = String::new();
+    if keys.len() == 1 {
+        if let Some((key, value)) = entry.properties.first() {
+            if !hide_key {
+                appendix += key;
+                appendix += ": ";
+            }
+            appendix += value;
+        }
+    } else {
+        for (key, value) in &entry.properties {
+            appendix += key;
+            appendix += ": ";
+            appendix += value;
+            appendix += "\t\t";
+        }
+    }
+    appendix
+}
+
+/// Join lines of text layouts, where appendices are aligned after the longest line
+pub fn align(strings_and_appendices: Vec<(String, String)>) -> String {
+    let max_len = strings_and_appendices.iter().fold(0, |pre_len, (name, _)| {
+        let len = name.chars().count();
+        if pre_len > len {
+            pre_len
+        } else {
+            len
+        }
+    });
+    let pre_len = max_len + 4;
+    strings_and_appendices.into_iter().map(|(name, appendix)| {
+        let len = name.chars().count();
+        let remain = pre_len - len;
+        name + &" ".repeat(remain) + &appendix
+    }).collect::<Vec<String>>().join("\n")
+}
@@ -19,6 +19,9 @@ lazy_static! {
     ];
 }
 
+/// Directory that keeps Wispha's own files, like the history of interact mode. It's never part of a project
+static WISPHA_DIR: &str = ".wispha";
+
 fn run() -> Result<(), Box<dyn error::Error>> {
     let opt = commandline::Commandline::from_args();
     opt.run()?;
@@ -28,6 +31,9 @@ fn run() -> Result<(), Box<dyn error::Error>> {
 fn main() {
     if let Err(error) = run() {
         eprintln!("Err: {}", error);
+        if let Some(snippet) = commandline::error_snippet(&*error) {
+            eprintln!("{}", snippet);
+        }
         std::process::exit(1);
     }
 }